@@ -7,20 +7,30 @@ use std::rc::Rc;
 
 pub type GridId = usize;
 pub type AreaId = i64;
+pub type OrganismId = usize; // index of the organism owning an area
 pub type AreaPtr = Rc<RefCell<Area>>;
 
 pub struct Area {
     pub area_id: AreaId, // unique id of this area(not to be confused with grid id)
     pub square: Square,
     pub grid_id: GridId,
+    pub organism_id: OrganismId,
+    pub symptomatic: bool, // visibly infected, used by organisms that avoid the infected
 }
 
 impl Area {
-    pub fn new(center: &Vector2<f32>, grid_id: GridId, size: f32) -> AreaPtr {
+    pub fn new(
+        center: &Vector2<f32>,
+        grid_id: GridId,
+        organism_id: OrganismId,
+        size: f32,
+    ) -> AreaPtr {
         let mut rng = rand::thread_rng();
         let area = Area {
             area_id: rng.gen::<AreaId>(),
             grid_id,
+            organism_id,
+            symptomatic: false,
             square: Square::new(center.clone(), size),
         };
         Rc::new(RefCell::new(area))
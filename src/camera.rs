@@ -0,0 +1,101 @@
+use rgx::math::*;
+// maps the arena onto the window, with zoom and pan so arenas larger than the screen can be explored
+
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 20.;
+
+pub struct Camera {
+    center: Vector2<f32>, // arena point shown in the middle of the window
+    zoom: f32,            // window pixels per arena pixel
+}
+
+impl Camera {
+    // centered on the arena, scaled so all of it fits in the window
+    pub fn fit(arena_size: Vector2<f32>, window_size: Vector2<f32>) -> Camera {
+        let zoom = (window_size.x / arena_size.x).min(window_size.y / arena_size.y);
+        Camera {
+            center: arena_size * 0.5,
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+        }
+    }
+
+    // arena to window coordinates, to be combined with the window's ortho projection
+    pub fn view(&self, window_size: Vector2<f32>) -> Matrix4<f32> {
+        let half_window = window_size * 0.5;
+        Matrix4::from_translation(Vector3::new(half_window.x, half_window.y, 0.))
+            * Matrix4::from_nonuniform_scale(self.zoom, self.zoom, 1.)
+            * Matrix4::from_translation(Vector3::new(-self.center.x, -self.center.y, 0.))
+    }
+
+    pub fn screen_to_world(&self, point: &Vector2<f32>, window_size: Vector2<f32>) -> Vector2<f32> {
+        (*point - window_size * 0.5) * (1. / self.zoom) + self.center
+    }
+
+    // zooms by factor while keeping the arena point under the cursor in place
+    pub fn zoom_at(&mut self, point: &Vector2<f32>, factor: f32, window_size: Vector2<f32>) {
+        let anchor = self.screen_to_world(point, window_size);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = anchor - (*point - window_size * 0.5) * (1. / self.zoom);
+    }
+
+    // moves the view along with a drag of delta window pixels
+    pub fn pan(&mut self, delta: &Vector2<f32>) {
+        self.center = self.center - *delta * (1. / self.zoom);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW_SIZE: Vector2<f32> = Vector2 { x: 800., y: 600. };
+
+    // the window point the view matrix puts an arena point at
+    fn world_to_screen(camera: &Camera, point: &Vector2<f32>) -> Vector2<f32> {
+        let point = camera.view(WINDOW_SIZE) * Point2::new(point.x, point.y);
+        Vector2::new(point.x, point.y)
+    }
+
+    fn assert_near(first: Vector2<f32>, second: Vector2<f32>) {
+        assert!(first.distance(second) < 1e-3, "{:?} != {:?}", first, second);
+    }
+
+    #[test]
+    fn screen_to_world_undoes_the_view() {
+        let mut camera = Camera::fit(Vector2::new(1024., 768.), WINDOW_SIZE);
+        camera.zoom_at(&Vector2::new(100., 200.), 3., WINDOW_SIZE);
+        camera.pan(&Vector2::new(-40., 25.));
+        for point in &[
+            Vector2::new(0., 0.),
+            Vector2::new(512., 384.),
+            Vector2::new(1000., 10.),
+        ] {
+            let screen = world_to_screen(&camera, point);
+            assert_near(camera.screen_to_world(&screen, WINDOW_SIZE), *point);
+        }
+    }
+
+    #[test]
+    fn zooming_keeps_the_point_under_the_cursor() {
+        let mut camera = Camera::fit(Vector2::new(1024., 768.), WINDOW_SIZE);
+        let cursor = Vector2::new(620., 130.);
+        let anchor = camera.screen_to_world(&cursor, WINDOW_SIZE);
+        for factor in &[2., 0.5, 0.1, 7.] {
+            camera.zoom_at(&cursor, *factor, WINDOW_SIZE);
+            assert_near(camera.screen_to_world(&cursor, WINDOW_SIZE), anchor);
+        }
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut camera = Camera::fit(Vector2::new(1024., 768.), WINDOW_SIZE);
+        let cursor = Vector2::new(400., 300.);
+        camera.zoom_at(&cursor, 1e6, WINDOW_SIZE);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+        camera.zoom_at(&cursor, 1e-9, WINDOW_SIZE);
+        assert_eq!(camera.zoom, MIN_ZOOM);
+
+        let camera = Camera::fit(Vector2::new(1e6, 1e6), WINDOW_SIZE);
+        assert_eq!(camera.zoom, MIN_ZOOM);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::organism::InfectionState;
+use crate::simulation_app::{SimulationApp, WindowAttributes};
+use rgx::core::*;
+use rgx::kit::shape2d::{Batch, Fill, Shape};
+use rgx::math::*;
+// live stacked-area epidemic chart drawn in a panel in the bottom right corner
+
+const WIDTH: f32 = 320.;
+const HEIGHT: f32 = 120.;
+const MARGIN: f32 = 8.;
+const COLUMN_WIDTH: f32 = 2.;
+
+pub fn render_chart(batch: &mut Batch, simulation: &SimulationApp, window: &WindowAttributes) {
+    let left = window.width as f32 - WIDTH - MARGIN * 3.;
+    let top = window.height as f32 - HEIGHT - MARGIN * 3.;
+    batch.add(
+        Shape::rect(
+            Point2::new(left, top),
+            Point2::new(left + WIDTH + MARGIN * 2., top + HEIGHT + MARGIN * 2.),
+        )
+        .fill(Fill::Solid(Rgba::new(0.0, 0.0, 0.0, 0.6))),
+    );
+
+    // the whole run is squeezed into the panel, one column per sampled tick
+    let theme = &simulation.config().theme;
+    let counts = simulation.counts();
+    let num_columns = ((WIDTH / COLUMN_WIDTH) as usize).min(counts.len());
+    let bottom = top + MARGIN + HEIGHT;
+    for column in 0..num_columns {
+        let sample = &counts[column * counts.len() / num_columns];
+        let population = sample.population() as f32;
+        if population == 0. {
+            continue;
+        }
+
+        let x = left + MARGIN + column as f32 * COLUMN_WIDTH;
+        let mut y = bottom;
+        // stacked from the bottom up
+        let layers = [
+            (sample.infected, InfectionState::Infected),
+            (sample.susceptible, InfectionState::Uninfected),
+            (sample.recovered, InfectionState::Recovered),
+            (sample.dead, InfectionState::Dead),
+        ];
+        for (count, state) in layers.iter() {
+            let height = HEIGHT * *count as f32 / population;
+            if height > 0. {
+                batch.add(
+                    Shape::rect(Point2::new(x, y - height), Point2::new(x + COLUMN_WIDTH, y))
+                        .fill(Fill::Solid(theme.color(*state))),
+                );
+            }
+            y -= height;
+        }
+    }
+}
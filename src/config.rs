@@ -0,0 +1,425 @@
+use crate::movement::MovementModelKind;
+use crate::organism::{FearResponse, OrganismShape, OrganismStyle, SelfIsolation};
+use crate::theme::{parse_color, Theme};
+use crate::window_box::BoundaryMode;
+use rgx::core::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+// every value the simulation is set up with, the defaults are what the app starts with
+
+#[derive(Clone)]
+pub struct SimulationConfig {
+    pub seed: Option<u64>, // a random seed is picked when None
+    pub arena_width: i32,  // independent of the window, which only decides how large it is drawn
+    pub arena_height: i32,
+    pub num_organisms: usize,
+    pub num_initially_infected: usize,
+    pub percent_in_place: f32,
+    pub organism_size: f32,
+    pub infection_lifetime_ms: i64,
+    pub symptom_onset_ms: i64,
+    pub self_isolation: SelfIsolation,
+    pub fatality_rate: f32,
+    pub grid_pixel_size: i32,
+    pub max_velocity: f32,
+    pub boundary_mode: BoundaryMode,
+    pub density_map_path: Option<String>,
+    pub movement_model: MovementModelKind,
+    pub fear_response: Option<FearResponse>,
+    pub curve_csv_path: Option<String>,
+    pub curve_json_path: Option<String>,
+    pub genealogy_edge_list_path: Option<String>,
+    pub genealogy_newick_path: Option<String>,
+    pub rt_window_ms: i64,
+    pub reproduction_json_path: Option<String>,
+    pub contact_radius: f32,
+    pub contact_graphml_path: Option<String>,
+    pub contact_dot_path: Option<String>,
+    pub trail_length_ms: i64, // simulated time a movement trail covers, 0 draws none
+    pub infection_ring_ms: i64, // how long a ring grows out of a new infection, 0 draws none
+    pub theme: Theme,
+    pub organism_style: OrganismStyle,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            seed: None,
+            arena_width: 1024,
+            arena_height: 768,
+            num_organisms: 6000,
+            num_initially_infected: 200,
+            percent_in_place: 90.,
+            organism_size: 3.0,
+            infection_lifetime_ms: 1000,
+            symptom_onset_ms: 400,
+            self_isolation: SelfIsolation {
+                compliance: 0.,
+                velocity_factor: 0.,
+            },
+            fatality_rate: 2.0,
+            grid_pixel_size: 25,
+            max_velocity: 100.,
+            boundary_mode: BoundaryMode::Reflect,
+            density_map_path: None,
+            movement_model: MovementModelKind::DirectionReset {
+                max_direction_ms: 7000,
+            },
+            fear_response: None,
+            curve_csv_path: None,
+            curve_json_path: None,
+            genealogy_edge_list_path: None,
+            genealogy_newick_path: None,
+            rt_window_ms: 500,
+            reproduction_json_path: None,
+            contact_radius: 6.,
+            contact_graphml_path: None,
+            contact_dot_path: None,
+            trail_length_ms: 0,
+            infection_ring_ms: 0,
+            theme: Theme::classic(),
+            organism_style: OrganismStyle {
+                shape: OrganismShape::Square,
+                size_by_velocity: false,
+                outline_isolating: false,
+            },
+        }
+    }
+}
+
+impl SimulationConfig {
+    // loads a scenario file of `key = value` lines on top of the defaults, `#` starts a comment
+    pub fn load(path: &str) -> Result<SimulationConfig, io::Error> {
+        let mut values = HashMap::new();
+        for (index, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap().trim();
+            let value = parts
+                .next()
+                .ok_or_else(|| invalid(format!("line {}: expected `key = value`", index + 1)))?
+                .trim();
+            values.insert(key.to_string(), value.to_string());
+        }
+
+        let mut config = SimulationConfig::default();
+        if let Some(seed) = take(&mut values, "seed")? {
+            config.seed = Some(seed);
+        }
+        set(&mut values, "arena_width", &mut config.arena_width)?;
+        set(&mut values, "arena_height", &mut config.arena_height)?;
+        set(&mut values, "num_organisms", &mut config.num_organisms)?;
+        set(
+            &mut values,
+            "num_initially_infected",
+            &mut config.num_initially_infected,
+        )?;
+        set(
+            &mut values,
+            "percent_in_place",
+            &mut config.percent_in_place,
+        )?;
+        set(&mut values, "organism_size", &mut config.organism_size)?;
+        set(
+            &mut values,
+            "infection_lifetime_ms",
+            &mut config.infection_lifetime_ms,
+        )?;
+        set(
+            &mut values,
+            "symptom_onset_ms",
+            &mut config.symptom_onset_ms,
+        )?;
+        set(
+            &mut values,
+            "isolation_compliance",
+            &mut config.self_isolation.compliance,
+        )?;
+        set(
+            &mut values,
+            "isolation_velocity_factor",
+            &mut config.self_isolation.velocity_factor,
+        )?;
+        set(&mut values, "fatality_rate", &mut config.fatality_rate)?;
+        set(&mut values, "grid_pixel_size", &mut config.grid_pixel_size)?;
+        set(&mut values, "max_velocity", &mut config.max_velocity)?;
+        if let Some(boundary_mode) = values.remove("boundary_mode") {
+            config.boundary_mode = match boundary_mode.as_str() {
+                "reflect" => BoundaryMode::Reflect,
+                "wrap" => BoundaryMode::Wrap,
+                _ => return Err(invalid(format!("unknown boundary_mode {}", boundary_mode))),
+            };
+        }
+        set_path(
+            &mut values,
+            "density_map_path",
+            &mut config.density_map_path,
+        );
+
+        if let Some(movement_model) = values.remove("movement_model") {
+            config.movement_model = match movement_model.as_str() {
+                "direction_reset" => MovementModelKind::DirectionReset {
+                    max_direction_ms: take(&mut values, "max_direction_ms")?.unwrap_or(7000),
+                },
+                "brownian" => MovementModelKind::Brownian,
+                "levy_flight" => MovementModelKind::LevyFlight {
+                    alpha: take(&mut values, "levy_alpha")?.unwrap_or(1.5),
+                    min_flight: take(&mut values, "levy_min_flight")?.unwrap_or(5.),
+                    max_flight: take(&mut values, "levy_max_flight")?.unwrap_or(1000.),
+                },
+                "correlated_random_walk" => MovementModelKind::CorrelatedRandomWalk {
+                    turning_rate: take(&mut values, "turning_rate")?.unwrap_or(1.),
+                },
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown movement_model {}",
+                        movement_model
+                    )))
+                }
+            };
+        }
+
+        if let Some(true) = take(&mut values, "fear_response")? {
+            config.fear_response = Some(FearResponse {
+                perception_radius: take(&mut values, "fear_perception_radius")?.unwrap_or(30.),
+                symptomatic_only: take(&mut values, "fear_symptomatic_only")?.unwrap_or(true),
+                sensitivity: take(&mut values, "fear_sensitivity")?.unwrap_or(10.),
+            });
+        }
+
+        set_path(&mut values, "curve_csv_path", &mut config.curve_csv_path);
+        set_path(&mut values, "curve_json_path", &mut config.curve_json_path);
+        set_path(
+            &mut values,
+            "genealogy_edge_list_path",
+            &mut config.genealogy_edge_list_path,
+        );
+        set_path(
+            &mut values,
+            "genealogy_newick_path",
+            &mut config.genealogy_newick_path,
+        );
+        set(&mut values, "rt_window_ms", &mut config.rt_window_ms)?;
+        set_path(
+            &mut values,
+            "reproduction_json_path",
+            &mut config.reproduction_json_path,
+        );
+        set(&mut values, "contact_radius", &mut config.contact_radius)?;
+        set_path(
+            &mut values,
+            "contact_graphml_path",
+            &mut config.contact_graphml_path,
+        );
+        set_path(
+            &mut values,
+            "contact_dot_path",
+            &mut config.contact_dot_path,
+        );
+        set(&mut values, "trail_length_ms", &mut config.trail_length_ms)?;
+        set(
+            &mut values,
+            "infection_ring_ms",
+            &mut config.infection_ring_ms,
+        )?;
+
+        // a named theme first, so single colours can be overridden on top of it
+        if let Some(theme) = values.remove("theme") {
+            config.theme =
+                Theme::named(&theme).ok_or_else(|| invalid(format!("unknown theme {}", theme)))?;
+        }
+        set_color(
+            &mut values,
+            "color_uninfected",
+            &mut config.theme.uninfected,
+        )?;
+        set_color(&mut values, "color_infected", &mut config.theme.infected)?;
+        set_color(
+            &mut values,
+            "color_symptomatic",
+            &mut config.theme.symptomatic,
+        )?;
+        set_color(&mut values, "color_recovered", &mut config.theme.recovered)?;
+        set_color(&mut values, "color_dead", &mut config.theme.dead)?;
+        set(&mut values, "blink_dead", &mut config.theme.blink_dead)?;
+
+        if let Some(shape) = values.remove("organism_shape") {
+            config.organism_style.shape = match shape.as_str() {
+                "square" => OrganismShape::Square,
+                "circle" => OrganismShape::Circle,
+                _ => return Err(invalid(format!("unknown organism_shape {}", shape))),
+            };
+        }
+        set(
+            &mut values,
+            "size_by_velocity",
+            &mut config.organism_style.size_by_velocity,
+        )?;
+        set(
+            &mut values,
+            "outline_isolating",
+            &mut config.organism_style.outline_isolating,
+        )?;
+
+        // anything left over is a typo or belongs to a model that wasn't selected
+        if let Some(key) = values.keys().next() {
+            return Err(invalid(format!("unused key {}", key)));
+        }
+        // the arena and grid are divided by these, and a window of zero estimates nothing
+        let mut positive = vec![
+            ("arena_width", config.arena_width > 0),
+            ("arena_height", config.arena_height > 0),
+            ("grid_pixel_size", config.grid_pixel_size > 0),
+            ("rt_window_ms", config.rt_window_ms > 0),
+        ];
+        if let MovementModelKind::LevyFlight {
+            alpha,
+            min_flight,
+            max_flight,
+        } = config.movement_model
+        {
+            // flights of length 0 would freeze the population
+            positive.push(("levy_alpha", alpha > 0.));
+            positive.push(("levy_min_flight", min_flight > 0.));
+            if max_flight < min_flight {
+                return Err(invalid(
+                    "levy_max_flight must be at least levy_min_flight".to_string(),
+                ));
+            }
+        }
+        if let Some((key, _)) = positive.iter().find(|(_, positive)| !positive) {
+            return Err(invalid(format!("{} must be positive", key)));
+        }
+        Ok(config)
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn take<T: FromStr>(
+    values: &mut HashMap<String, String>,
+    key: &str,
+) -> Result<Option<T>, io::Error> {
+    match values.remove(key) {
+        Some(value) => match value.parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(invalid(format!("invalid value for {}: {}", key, value))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn set<T: FromStr>(
+    values: &mut HashMap<String, String>,
+    key: &str,
+    field: &mut T,
+) -> Result<(), io::Error> {
+    if let Some(value) = take(values, key)? {
+        *field = value;
+    }
+    Ok(())
+}
+
+fn set_color(
+    values: &mut HashMap<String, String>,
+    key: &str,
+    field: &mut Rgba,
+) -> Result<(), io::Error> {
+    if let Some(value) = values.remove(key) {
+        *field = parse_color(&value)
+            .ok_or_else(|| invalid(format!("invalid colour for {}: {}", key, value)))?;
+    }
+    Ok(())
+}
+
+// `none` clears a path
+fn set_path(values: &mut HashMap<String, String>, key: &str, field: &mut Option<String>) {
+    if let Some(value) = values.remove(key) {
+        *field = if value == "none" { None } else { Some(value) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // tests run in parallel, so every scenario gets a file of its own
+    static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+    fn load(contents: &str) -> Result<SimulationConfig, io::Error> {
+        let path = std::env::temp_dir().join(format!(
+            "scenario_{}_{}.txt",
+            std::process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&path, contents).unwrap();
+        let config = SimulationConfig::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn values_override_the_defaults() {
+        let config = load("# a scenario\nnum_organisms = 10\nseed = 7 # fixed\n\n").unwrap();
+        assert_eq!(config.num_organisms, 10);
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.arena_width, SimulationConfig::default().arena_width);
+    }
+
+    #[test]
+    fn none_clears_a_path() {
+        let config = load("curve_csv_path = curve.csv\ncurve_json_path = none\n").unwrap();
+        assert_eq!(config.curve_csv_path.as_deref(), Some("curve.csv"));
+        assert_eq!(config.curve_json_path, None);
+    }
+
+    #[test]
+    fn colours_override_the_named_theme() {
+        let config = load("color_dead = ff0000\ntheme = classic\n").unwrap();
+        assert_eq!(config.theme.dead, Rgba::new(1., 0., 0., 1.));
+        assert_eq!(config.theme.infected, Theme::classic().infected);
+    }
+
+    #[test]
+    fn levy_flight_parameters() {
+        let config = load("movement_model = levy_flight\nlevy_alpha = 2\n").unwrap();
+        match config.movement_model {
+            MovementModelKind::LevyFlight {
+                alpha,
+                min_flight,
+                max_flight,
+            } => assert_eq!((alpha, min_flight, max_flight), (2., 5., 1000.)),
+            _ => panic!("not a Lévy flight"),
+        }
+    }
+
+    #[test]
+    fn invalid_scenarios_are_rejected() {
+        for contents in &[
+            "unknown_key = 1\n",
+            "num_organisms = many\n",
+            "num_organisms\n",
+            "boundary_mode = bounce\n",
+            "max_direction_ms = 100\n",
+            "color_dead = #ff0000\n",
+            "grid_pixel_size = 0\n",
+            "rt_window_ms = 0\n",
+            "arena_width = 0\n",
+            "arena_height = -1\n",
+            "movement_model = levy_flight\nlevy_min_flight = 0\n",
+            "movement_model = levy_flight\nlevy_alpha = -1\n",
+            "movement_model = levy_flight\nlevy_min_flight = 10\nlevy_max_flight = 5\n",
+        ] {
+            let error = load(contents).err().expect(contents);
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", contents);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::area::OrganismId;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+// proximity contacts between organisms aggregated over the run, exportable as GraphML or DOT
+
+pub struct ContactRecord {
+    pub duration_ms: i64, // total time spent in contact
+    pub episodes: usize,  // number of separate times the organisms came into contact
+    last_tick: u64,
+}
+
+#[derive(Default)]
+pub struct ContactNetwork {
+    contacts: HashMap<(OrganismId, OrganismId), ContactRecord>,
+    tick: u64,
+}
+
+impl ContactNetwork {
+    // call once per tick before recording that tick's contacts
+    pub fn next_tick(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn record(&mut self, first: OrganismId, second: OrganismId, delta_ms: i64) {
+        let key = (first.min(second), first.max(second));
+        let tick = self.tick;
+        let contact = self.contacts.entry(key).or_insert(ContactRecord {
+            duration_ms: 0,
+            episodes: 0,
+            last_tick: 0,
+        });
+        // a contact that was already there on the previous tick continues the same episode
+        if contact.episodes == 0 || contact.last_tick + 1 < tick {
+            contact.episodes += 1;
+        }
+        if contact.last_tick != tick {
+            contact.duration_ms += delta_ms;
+            contact.last_tick = tick;
+        }
+    }
+
+    pub fn write_graphml(&self, path: &str, num_organisms: usize) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            file,
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+        )?;
+        writeln!(
+            file,
+            "  <key id=\"duration_ms\" for=\"edge\" attr.name=\"duration_ms\" attr.type=\"long\"/>"
+        )?;
+        writeln!(
+            file,
+            "  <key id=\"episodes\" for=\"edge\" attr.name=\"episodes\" attr.type=\"int\"/>"
+        )?;
+        writeln!(file, "  <graph id=\"contacts\" edgedefault=\"undirected\">")?;
+        for organism_id in 0..num_organisms {
+            writeln!(file, "    <node id=\"n{}\"/>", organism_id)?;
+        }
+        for ((first, second), contact) in self.sorted_contacts() {
+            writeln!(
+                file,
+                "    <edge source=\"n{}\" target=\"n{}\">",
+                first, second
+            )?;
+            writeln!(
+                file,
+                "      <data key=\"duration_ms\">{}</data>",
+                contact.duration_ms
+            )?;
+            writeln!(
+                file,
+                "      <data key=\"episodes\">{}</data>",
+                contact.episodes
+            )?;
+            writeln!(file, "    </edge>")?;
+        }
+        writeln!(file, "  </graph>")?;
+        writeln!(file, "</graphml>")?;
+        file.flush()
+    }
+
+    pub fn write_dot(&self, path: &str, num_organisms: usize) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "graph contacts {{")?;
+        for organism_id in 0..num_organisms {
+            writeln!(file, "  {};", organism_id)?;
+        }
+        for ((first, second), contact) in self.sorted_contacts() {
+            writeln!(
+                file,
+                "  {} -- {} [duration_ms={}, episodes={}];",
+                first, second, contact.duration_ms, contact.episodes
+            )?;
+        }
+        writeln!(file, "}}")?;
+        file.flush()
+    }
+
+    // sorted so repeated exports of the same run are identical
+    fn sorted_contacts(&self) -> Vec<(&(OrganismId, OrganismId), &ContactRecord)> {
+        let mut contacts: Vec<_> = self.contacts.iter().collect();
+        contacts.sort_by_key(|(key, _)| **key);
+        contacts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(network: &ContactNetwork, first: OrganismId, second: OrganismId) -> (i64, usize) {
+        let contact = &network.contacts[&(first, second)];
+        (contact.duration_ms, contact.episodes)
+    }
+
+    #[test]
+    fn consecutive_ticks_are_one_episode() {
+        let mut network = ContactNetwork::default();
+        for _ in 0..3 {
+            network.next_tick();
+            network.record(1, 2, 16);
+        }
+        assert_eq!(contact(&network, 1, 2), (48, 1));
+    }
+
+    #[test]
+    fn a_gap_starts_a_new_episode() {
+        let mut network = ContactNetwork::default();
+        network.next_tick();
+        network.record(1, 2, 16);
+        network.next_tick();
+        network.next_tick();
+        network.record(1, 2, 16);
+        assert_eq!(contact(&network, 1, 2), (32, 2));
+    }
+
+    #[test]
+    fn pairs_are_unordered_and_counted_once_per_tick() {
+        let mut network = ContactNetwork::default();
+        network.next_tick();
+        network.record(2, 1, 16);
+        network.record(1, 2, 16);
+        assert_eq!(network.contacts.len(), 1);
+        assert_eq!(contact(&network, 1, 2), (16, 1));
+    }
+
+    #[test]
+    fn dot_export_is_sorted() {
+        let mut network = ContactNetwork::default();
+        network.next_tick();
+        network.record(3, 1, 16);
+        network.record(0, 2, 16);
+        let path = std::env::temp_dir().join(format!("contacts_{}.dot", std::process::id()));
+        network.write_dot(path.to_str().unwrap(), 4).unwrap();
+        let dot = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            dot,
+            "graph contacts {\n  0;\n  1;\n  2;\n  3;\n  0 -- 2 [duration_ms=16, episodes=1];\n  1 -- 3 [duration_ms=16, episodes=1];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::statistics::CompartmentCounts;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+// writes the epidemic curve to CSV and/or JSON as the simulation runs
+
+pub struct CurveWriter {
+    csv: Option<BufWriter<File>>,
+    json: Option<BufWriter<File>>,
+    num_json_entries: usize,
+}
+
+impl CurveWriter {
+    pub fn create(
+        csv_path: Option<&str>,
+        json_path: Option<&str>,
+    ) -> Result<CurveWriter, io::Error> {
+        let csv = match csv_path {
+            Some(path) => {
+                let mut csv = BufWriter::new(File::create(path)?);
+                writeln!(
+                    csv,
+                    "time_ms,susceptible,infected,symptomatic,recovered,dead,new_infections,cumulative_infections,cumulative_deaths"
+                )?;
+                Some(csv)
+            }
+            None => None,
+        };
+        let json = match json_path {
+            Some(path) => {
+                let mut json = BufWriter::new(File::create(path)?);
+                write!(json, "[")?;
+                Some(json)
+            }
+            None => None,
+        };
+
+        Ok(CurveWriter {
+            csv,
+            json,
+            num_json_entries: 0,
+        })
+    }
+
+    pub fn write(&mut self, counts: &CompartmentCounts) -> Result<(), io::Error> {
+        if let Some(csv) = self.csv.as_mut() {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{}",
+                counts.time_ms,
+                counts.susceptible,
+                counts.infected,
+                counts.symptomatic,
+                counts.recovered,
+                counts.dead,
+                counts.new_infections,
+                counts.cumulative_infections,
+                counts.cumulative_deaths,
+            )?;
+        }
+        if let Some(json) = self.json.as_mut() {
+            let separator = if self.num_json_entries == 0 { "" } else { "," };
+            write!(
+                json,
+                "{}\n  {{\"time_ms\": {}, \"susceptible\": {}, \"infected\": {}, \"symptomatic\": {}, \"recovered\": {}, \"dead\": {}, \"new_infections\": {}, \"cumulative_infections\": {}, \"cumulative_deaths\": {}}}",
+                separator,
+                counts.time_ms,
+                counts.susceptible,
+                counts.infected,
+                counts.symptomatic,
+                counts.recovered,
+                counts.dead,
+                counts.new_infections,
+                counts.cumulative_infections,
+                counts.cumulative_deaths,
+            )?;
+            self.num_json_entries += 1;
+        }
+        Ok(())
+    }
+
+    // closes the JSON array and flushes both files, call once when the run ends
+    pub fn finish(&mut self) -> Result<(), io::Error> {
+        if let Some(csv) = self.csv.as_mut() {
+            csv.flush()?;
+        }
+        if let Some(json) = self.json.as_mut() {
+            write!(json, "\n]\n")?;
+            json.flush()?;
+        }
+        self.csv = None;
+        self.json = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("curve_{}_{}", std::process::id(), name))
+    }
+
+    fn counts(time_ms: i64, dead: usize, cumulative_deaths: usize) -> CompartmentCounts {
+        CompartmentCounts {
+            time_ms,
+            susceptible: 10,
+            infected: 5,
+            symptomatic: 2,
+            recovered: 3,
+            dead,
+            new_infections: 1,
+            cumulative_infections: 9,
+            cumulative_deaths,
+        }
+    }
+
+    #[test]
+    fn writes_a_csv_row_per_tick() {
+        let path = temp_path("rows.csv");
+        let mut writer = CurveWriter::create(path.to_str(), None).unwrap();
+        writer.write(&counts(0, 1, 1)).unwrap();
+        writer.write(&counts(16, 1, 4)).unwrap();
+        writer.finish().unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "time_ms,susceptible,infected,symptomatic,recovered,dead,new_infections,cumulative_infections,cumulative_deaths",
+                "0,10,5,2,3,1,1,9,1",
+                "16,10,5,2,3,1,1,9,4",
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_a_closed_json_array() {
+        let path = temp_path("rows.json");
+        let mut writer = CurveWriter::create(None, path.to_str()).unwrap();
+        writer.write(&counts(0, 0, 0)).unwrap();
+        writer.write(&counts(16, 2, 2)).unwrap();
+        writer.finish().unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(json.starts_with("[\n  {\"time_ms\": 0,"));
+        assert!(json.ends_with("\"cumulative_deaths\": 2}\n]\n"));
+        assert_eq!(json.matches("},\n  {").count(), 1);
+    }
+
+    #[test]
+    fn an_empty_json_curve_is_an_empty_array() {
+        let path = temp_path("empty.json");
+        let mut writer = CurveWriter::create(None, path.to_str()).unwrap();
+        writer.finish().unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(json, "[\n]\n");
+    }
+}
@@ -0,0 +1,153 @@
+use rand::prelude::*;
+use rgx::math::*;
+use std::fs::File;
+use std::io;
+// samples positions from a grayscale image where brighter pixels mean denser population
+
+pub struct DensityMap {
+    width: usize,
+    height: usize,
+    cumulative: Vec<u64>, // running sum of pixel densities, used to pick a pixel by weight
+}
+
+impl DensityMap {
+    pub fn load(path: &str) -> Result<DensityMap, io::Error> {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buffer)?;
+
+        let channels = match reader.output_color_type().0 {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::RGB => 3,
+            png::ColorType::RGBA => 4,
+            png::ColorType::Indexed => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "indexed density maps are not supported",
+                ))
+            }
+        };
+
+        let width = info.width as usize;
+        let height = info.height as usize;
+        let mut cumulative = Vec::with_capacity(width * height);
+        let mut total = 0;
+        for pixel in buffer.chunks(channels).take(width * height) {
+            // colour images are reduced to their average brightness
+            let density = if channels >= 3 {
+                (pixel[0] as u64 + pixel[1] as u64 + pixel[2] as u64) / 3
+            } else {
+                pixel[0] as u64
+            };
+            total += density;
+            cumulative.push(total);
+        }
+
+        if total == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "density map has no populated pixels",
+            ));
+        }
+
+        Ok(DensityMap {
+            width,
+            height,
+            cumulative,
+        })
+    }
+
+    // picks a pixel weighted by its density, then a uniform point inside it scaled to the arena
+    pub fn sample<R: Rng>(&self, width: f32, height: f32, rng: &mut R) -> Vector2<f32> {
+        let total = *self.cumulative.last().unwrap();
+        let target = rng.gen_range(0, total);
+        let index = self.cumulative.partition_point(|sum| *sum <= target);
+
+        let pixel_x = (index % self.width) as f32 + rng.gen::<f32>();
+        let pixel_y = (index / self.width) as f32 + rng.gen::<f32>();
+        Vector2::new(
+            width * pixel_x / self.width as f32,
+            height * pixel_y / self.height as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use std::io::BufWriter;
+
+    // a grayscale png in the temporary directory, removed when dropped
+    struct TempMap(std::path::PathBuf);
+
+    impl TempMap {
+        fn write(name: &str, width: u32, height: u32, pixels: &[u8]) -> TempMap {
+            let path = std::env::temp_dir().join(format!(
+                "density_map_{}_{}.png",
+                name,
+                std::process::id()
+            ));
+            let file = BufWriter::new(File::create(&path).unwrap());
+            let mut encoder = png::Encoder::new(file, width, height);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder
+                .write_header()
+                .unwrap()
+                .write_image_data(pixels)
+                .unwrap();
+            TempMap(path)
+        }
+
+        fn load(&self) -> Result<DensityMap, io::Error> {
+            DensityMap::load(self.0.to_str().unwrap())
+        }
+    }
+
+    impl Drop for TempMap {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn samples_only_populated_pixels() {
+        // only the bottom right pixel of a 2x2 map is populated
+        let map = TempMap::write("single", 2, 2, &[0, 0, 0, 255])
+            .load()
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let position = map.sample(100., 50., &mut rng);
+            assert!(position.x >= 50. && position.x < 100.);
+            assert!(position.y >= 25. && position.y < 50.);
+        }
+    }
+
+    #[test]
+    fn samples_in_proportion_to_brightness() {
+        let map = TempMap::write("weighted", 2, 1, &[64, 192]).load().unwrap();
+        let mut rng = StdRng::seed_from_u64(2);
+        let right = (0..10000)
+            .filter(|_| map.sample(2., 1., &mut rng).x >= 1.)
+            .count();
+        assert!(
+            (7000..8000).contains(&right),
+            "{} of 10000 on the right",
+            right
+        );
+    }
+
+    #[test]
+    fn an_empty_map_is_an_error() {
+        let map = TempMap::write("empty", 2, 2, &[0; 4]);
+        assert_eq!(
+            map.load().err().map(|error| error.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use crate::genealogy::InfectionEvent;
+use crate::organism::OrganismState;
+use crate::painter::Painter;
+use crate::theme::Theme;
+use rgx::core::*;
+use rgx::math::*;
+use std::collections::VecDeque;
+// optional visual effects: fading trails behind moving organisms and rings where infections happen
+
+// how often trail points are taken, three ticks
+const TRAIL_SAMPLE_MS: i64 = 48;
+const TRAIL_WIDTH: f32 = 1.;
+const TRAIL_ALPHA: f32 = 0.6; // of the newest segment, older ones fade out
+const RING_GROWTH: f32 = 20.; // pixels the ring grows beyond the organism
+const RING_WIDTH: f32 = 1.5;
+
+pub struct Trails {
+    length: usize, // points kept per organism
+    points: Vec<VecDeque<Vector2<f32>>>,
+    next_sample_ms: i64,
+}
+
+impl Trails {
+    pub fn new(length_ms: i64, num_organisms: usize) -> Trails {
+        Trails {
+            length: (length_ms / TRAIL_SAMPLE_MS).max(1) as usize,
+            points: vec![VecDeque::new(); num_organisms],
+            next_sample_ms: 0,
+        }
+    }
+
+    pub fn record(&mut self, time_ms: i64, organisms: &[OrganismState]) {
+        if time_ms < self.next_sample_ms {
+            return;
+        }
+        self.next_sample_ms = time_ms + TRAIL_SAMPLE_MS;
+        for (points, organism) in self.points.iter_mut().zip(organisms.iter()) {
+            if points.len() == self.length {
+                points.pop_front();
+            }
+            points.push_back(organism.position);
+        }
+    }
+
+    // each trail ends at the organism's current position, in the colour of its state
+    pub fn render<P: Painter>(
+        &self,
+        painter: &mut P,
+        organisms: &[OrganismState],
+        arena_size: Vector2<f32>,
+        theme: &Theme,
+    ) {
+        // longer jumps are organisms wrapping around the arena's edges
+        let max_jump = arena_size.x.min(arena_size.y) * 0.5;
+        for (points, organism) in self.points.iter().zip(organisms.iter()) {
+            let color = theme.color(organism.infection_state());
+            let segments = points.len();
+            let ends = points.iter().skip(1).chain(Some(&organism.position));
+            for (index, (start, end)) in points.iter().zip(ends).enumerate() {
+                if start == end || start.distance(*end) > max_jump {
+                    continue;
+                }
+                let alpha = TRAIL_ALPHA * (index + 1) as f32 / segments as f32;
+                painter.line(
+                    start,
+                    end,
+                    TRAIL_WIDTH,
+                    Rgba::new(color.r, color.g, color.b, alpha),
+                );
+            }
+        }
+    }
+}
+
+// a ring growing out of every infection younger than ring_ms, fading as it grows
+pub fn render_infection_rings<P: Painter>(
+    painter: &mut P,
+    events: &[InfectionEvent],
+    time_ms: i64,
+    ring_ms: i64,
+    organism_size: f32,
+    theme: &Theme,
+) {
+    let color = theme.infected;
+    // events are recorded in time order, so the recent ones are at the end
+    for event in events.iter().rev() {
+        let age_ms = time_ms - event.time_ms;
+        if age_ms >= ring_ms {
+            break;
+        }
+        let progress = age_ms.max(0) as f32 / ring_ms as f32;
+        painter.stroke_circle(
+            &event.position,
+            organism_size + progress * RING_GROWTH,
+            RING_WIDTH,
+            Rgba::new(color.r, color.g, color.b, 1. - progress),
+        );
+    }
+}
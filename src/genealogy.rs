@@ -0,0 +1,189 @@
+use crate::area::OrganismId;
+use rgx::math::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+// records who infected whom, exportable as an edge list or a Newick transmission tree
+
+pub struct InfectionEvent {
+    pub infector: Option<OrganismId>, // None for the initially infected and manual infections
+    pub infectee: OrganismId,
+    pub time_ms: i64,
+    pub position: Vector2<f32>, // where the infectee was infected
+    pub manual: bool,           // infected by clicking it during the run
+}
+
+// an event at the origin, for the tests of everything built on the genealogy
+#[cfg(test)]
+pub fn event(infector: Option<OrganismId>, infectee: OrganismId, time_ms: i64) -> InfectionEvent {
+    InfectionEvent {
+        infector,
+        infectee,
+        time_ms,
+        position: Vector2::new(0., 0.),
+        manual: false,
+    }
+}
+
+#[derive(Default)]
+pub struct Genealogy {
+    events: Vec<InfectionEvent>,
+}
+
+impl Genealogy {
+    pub fn record(&mut self, event: InfectionEvent) {
+        self.events.push(event);
+    }
+
+    // in the order the infections happened
+    pub fn events(&self) -> &[InfectionEvent] {
+        &self.events
+    }
+
+    // an organism is infected at most once, as recovery makes it immune
+    pub fn infection_of(&self, organism_id: OrganismId) -> Option<&InfectionEvent> {
+        self.events
+            .iter()
+            .find(|event| event.infectee == organism_id)
+    }
+
+    pub fn write_edge_list(&self, path: &str) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "infector,infectee,time_ms,x,y")?;
+        for event in self.events.iter() {
+            let infector = match event.infector {
+                Some(infector) => infector.to_string(),
+                None => String::new(),
+            };
+            writeln!(
+                file,
+                "{},{},{},{},{}",
+                infector, event.infectee, event.time_ms, event.position.x, event.position.y
+            )?;
+        }
+        file.flush()
+    }
+
+    // every initially infected organism roots its own tree, all of them joined under an unnamed
+    // root. Node labels are organism ids, branch lengths the milliseconds since the parent's infection
+    pub fn write_newick(&self, path: &str) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", self.newick())?;
+        file.flush()
+    }
+
+    // built with an explicit stack, as transmission chains can be far deeper than the call stack
+    fn newick(&self) -> String {
+        let mut children: HashMap<OrganismId, Vec<&InfectionEvent>> = HashMap::new();
+        let mut roots = vec![];
+        for event in self.events.iter() {
+            match event.infector {
+                Some(infector) => children.entry(infector).or_default().push(event),
+                None => roots.push(event),
+            }
+        }
+
+        // an event is entered before its infectees are written and left after them
+        enum Step<'a> {
+            Enter(&'a InfectionEvent, i64, bool), // parent's infection time, first sibling
+            Leave(&'a InfectionEvent, i64),
+        }
+        let mut newick = String::from("(");
+        let mut steps: Vec<Step> = roots
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, root)| Step::Enter(root, 0, index == 0))
+            .collect();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Enter(event, parent_time_ms, first) => {
+                    if !first {
+                        newick.push(',');
+                    }
+                    match children.get(&event.infectee) {
+                        Some(infectees) => {
+                            newick.push('(');
+                            steps.push(Step::Leave(event, parent_time_ms));
+                            for (index, infectee) in infectees.iter().enumerate().rev() {
+                                steps.push(Step::Enter(infectee, event.time_ms, index == 0));
+                            }
+                        }
+                        None => Self::push_newick_label(&mut newick, event, parent_time_ms),
+                    }
+                }
+                Step::Leave(event, parent_time_ms) => {
+                    newick.push(')');
+                    Self::push_newick_label(&mut newick, event, parent_time_ms);
+                }
+            }
+        }
+        newick.push_str(");");
+        newick
+    }
+
+    fn push_newick_label(newick: &mut String, event: &InfectionEvent, parent_time_ms: i64) {
+        newick.push_str(&format!(
+            "{}:{}",
+            event.infectee,
+            event.time_ms - parent_time_ms
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genealogy(events: Vec<InfectionEvent>) -> Genealogy {
+        let mut genealogy = Genealogy::default();
+        for event in events {
+            genealogy.record(event);
+        }
+        genealogy
+    }
+
+    #[test]
+    fn an_empty_genealogy_is_an_empty_tree() {
+        assert_eq!(Genealogy::default().newick(), "();");
+    }
+
+    #[test]
+    fn branch_lengths_are_times_since_the_parent() {
+        let genealogy = genealogy(vec![
+            event(None, 1, 0),
+            event(None, 2, 0),
+            event(Some(1), 3, 100),
+            event(Some(1), 4, 150),
+            event(Some(3), 5, 400),
+        ]);
+        assert_eq!(genealogy.newick(), "(((5:300)3:100,4:150)1:0,2:0);");
+    }
+
+    #[test]
+    fn long_chains_do_not_overflow_the_stack() {
+        let length = 200_000;
+        let mut events = vec![event(None, 0, 0)];
+        for organism_id in 1..length {
+            events.push(event(
+                Some(organism_id - 1),
+                organism_id,
+                organism_id as i64,
+            ));
+        }
+        let newick = genealogy(events).newick();
+        assert!(newick.starts_with(&"(".repeat(length)));
+        assert!(newick.ends_with(")0:0);"));
+    }
+
+    #[test]
+    fn infection_of_finds_the_infectee() {
+        let genealogy = genealogy(vec![event(None, 1, 0), event(Some(1), 2, 50)]);
+        assert_eq!(
+            genealogy.infection_of(2).map(|event| event.time_ms),
+            Some(50)
+        );
+        assert!(genealogy.infection_of(3).is_none());
+    }
+}
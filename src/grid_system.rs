@@ -1,5 +1,6 @@
-use crate::area::{AreaId, AreaPtr, GridId};
+use crate::area::{Area, AreaId, AreaPtr, GridId, OrganismId};
 use crate::square::Square;
+use crate::window_box::BoundaryMode;
 use rgx::math::*;
 use std::rc::Rc;
 
@@ -13,10 +14,14 @@ pub struct GridSystem {
     grids: Vec<Grid>,
     grid_size: f32,
     num_columns: i32,
+    num_rows: i32,
+    width: f32,
+    height: f32,
+    boundary_mode: BoundaryMode,
 }
 
 impl GridSystem {
-    pub fn new(width: i32, height: i32, grid_size: i32) -> GridSystem {
+    pub fn new(width: i32, height: i32, grid_size: i32, boundary_mode: BoundaryMode) -> GridSystem {
         // adding one to cover 0 to n, rather than 0 to n - 1
         let adjusted_width = ((width / grid_size) + 1) as i32;
         let adjusted_height = ((height / grid_size) + 1) as i32;
@@ -41,16 +46,53 @@ impl GridSystem {
         GridSystem {
             grids,
             num_columns: adjusted_width,
+            num_rows: adjusted_height,
             grid_size: grid_size as f32,
+            width: width as f32,
+            height: height as f32,
+            boundary_mode,
         }
     }
 
     pub fn get_grid_index(&self, position: &Vector2<f32>) -> usize {
+        let position = if self.boundary_mode == BoundaryMode::Wrap {
+            Vector2::new(
+                position.x.rem_euclid(self.width),
+                position.y.rem_euclid(self.height),
+            )
+        } else {
+            *position
+        };
         let x = (position.x as i32) / (self.grid_size as i32);
         let y = (position.y as i32) / (self.grid_size as i32);
         (x + (y * self.num_columns)) as GridId
     }
 
+    pub fn grid_size(&self) -> f32 {
+        self.grid_size
+    }
+
+    pub fn num_cells(&self) -> usize {
+        (self.num_columns * self.num_rows) as usize
+    }
+
+    pub fn get_cell_square(&self, grid_id: GridId) -> Square {
+        let x = (grid_id as i32 % self.num_columns) as f32;
+        let y = (grid_id as i32 / self.num_columns) as f32;
+        let half_grid_size = self.grid_size * 0.5;
+        Square::new(
+            Vector2::new(
+                x * self.grid_size + half_grid_size,
+                y * self.grid_size + half_grid_size,
+            ),
+            self.grid_size,
+        )
+    }
+
+    pub fn num_areas_in_grid(&self, grid_id: GridId) -> usize {
+        self.grids.get(grid_id).map_or(0, |grid| grid.radii.len())
+    }
+
     pub fn get_grid_id_list(&self, square: &Square) -> Vec<GridId> {
         let mut ids = vec![
             self.get_grid_index(&square.bottom_left),
@@ -64,6 +106,94 @@ impl GridSystem {
         ids
     }
 
+    // with wrap-around boundaries a square near an edge also touches the opposite edge,
+    // so return its images shifted across every edge its neighbourhood crosses
+    pub fn get_wrapped_squares(&self, square: &Square) -> Vec<Square> {
+        self.get_wrap_offsets(&square.add_half_size_bias())
+            .iter()
+            .map(|offset| square.translated(offset))
+            .collect()
+    }
+
+    // the untranslated offset always comes first
+    fn get_wrap_offsets(&self, square: &Square) -> Vec<Vector2<f32>> {
+        if self.boundary_mode != BoundaryMode::Wrap {
+            return vec![Vector2::new(0., 0.)];
+        }
+
+        let mut x_offsets = vec![0.];
+        let mut y_offsets = vec![0.];
+        if square.bottom_left.x < 0. {
+            x_offsets.push(self.width);
+        }
+        if square.top_right.x > self.width {
+            x_offsets.push(-self.width);
+        }
+        if square.bottom_left.y < 0. {
+            y_offsets.push(self.height);
+        }
+        if square.top_right.y > self.height {
+            y_offsets.push(-self.height);
+        }
+
+        let mut offsets = vec![];
+        for x in x_offsets.iter() {
+            for y in y_offsets.iter() {
+                offsets.push(Vector2::new(*x, *y));
+            }
+        }
+        offsets
+    }
+
+    // offsets from position to the center of every area within radius that passes the filter
+    pub fn find_offsets_in_radius<F>(
+        &self,
+        position: &Vector2<f32>,
+        radius: f32,
+        filter: F,
+    ) -> Vec<Vector2<f32>>
+    where
+        F: Fn(&Area) -> bool,
+    {
+        let mut found = vec![];
+        self.for_each_area_in_radius(position, radius, |area, offset| {
+            if filter(area) {
+                found.push(*offset);
+            }
+        });
+        found
+    }
+
+    // visits every area whose center is within radius, along with the offset from position to it
+    pub fn for_each_area_in_radius<F>(&self, position: &Vector2<f32>, radius: f32, mut visit: F)
+    where
+        F: FnMut(&Area, &Vector2<f32>),
+    {
+        let bounds = Square::new(*position, radius * 2.);
+        let grid_size = self.grid_size as i32;
+        for wrap_offset in self.get_wrap_offsets(&bounds).iter() {
+            let bounds = bounds.translated(wrap_offset);
+            let center = bounds.center;
+            let min_x = ((bounds.bottom_left.x as i32) / grid_size).max(0);
+            let max_x = ((bounds.top_right.x as i32) / grid_size).min(self.num_columns - 1);
+            let min_y = ((bounds.bottom_left.y as i32) / grid_size).max(0);
+            let max_y = ((bounds.top_right.y as i32) / grid_size).min(self.num_rows - 1);
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    if let Some(grid) = self.grids.get((x + (y * self.num_columns)) as GridId) {
+                        for area in grid.radii.iter() {
+                            let area = area.borrow();
+                            let offset = area.square.center - center;
+                            if offset.magnitude() <= radius {
+                                visit(&area, &offset);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub fn add_area(&mut self, area: &AreaPtr, grid_id: GridId) -> GridId {
         if let Some(grid) = self.grids.get_mut(grid_id) {
             grid.radii.push(Rc::clone(area));
@@ -71,6 +201,12 @@ impl GridSystem {
         grid_id
     }
 
+    pub fn clear(&mut self) {
+        for grid in self.grids.iter_mut() {
+            grid.radii.clear();
+        }
+    }
+
     pub fn remove_area_from_grid(&mut self, area_id: AreaId, grid_id: GridId) {
         if let Some(grid) = self.grids.get_mut(grid_id) {
             for index in 0..grid.radii.len() {
@@ -84,15 +220,114 @@ impl GridSystem {
         }
     }
 
-    pub fn find_intersection_in_grid(&self, grid_id: GridId, square: &Square) -> bool {
+    // returns the organism owning the first area intersecting square
+    pub fn find_intersection_in_grid(
+        &self,
+        grid_id: GridId,
+        square: &Square,
+    ) -> Option<OrganismId> {
         if let Some(grid) = self.grids.get(grid_id) {
             for area in grid.radii.iter() {
-                if (&*area).borrow().square.intersects(square) {
-                    return true;
+                let area = area.borrow();
+                if area.square.intersects(square) {
+                    return Some(area.organism_id);
                 }
             }
-            return false;
+            return None;
         }
-        false
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_area(grid_system: &mut GridSystem, center: Vector2<f32>, symptomatic: bool) {
+        let grid_id = grid_system.get_grid_index(&center);
+        let area = Area::new(&center, grid_id, 1, 3.);
+        area.borrow_mut().symptomatic = symptomatic;
+        grid_system.add_area(&area, grid_id);
+    }
+
+    fn offsets(boundary_mode: BoundaryMode, center: Vector2<f32>) -> Vec<Vector2<f32>> {
+        let grid_system = GridSystem::new(100, 80, 10, boundary_mode);
+        grid_system.get_wrap_offsets(&Square::new(center, 4.))
+    }
+
+    #[test]
+    fn reflect_never_wraps() {
+        assert_eq!(
+            offsets(BoundaryMode::Reflect, Vector2::new(0., 0.)),
+            vec![Vector2::new(0., 0.)]
+        );
+    }
+
+    #[test]
+    fn wrap_inside_the_arena_has_no_copies() {
+        assert_eq!(
+            offsets(BoundaryMode::Wrap, Vector2::new(50., 40.)),
+            vec![Vector2::new(0., 0.)]
+        );
+    }
+
+    #[test]
+    fn wrap_across_one_edge() {
+        assert_eq!(
+            offsets(BoundaryMode::Wrap, Vector2::new(99., 40.)),
+            vec![Vector2::new(0., 0.), Vector2::new(-100., 0.)]
+        );
+    }
+
+    #[test]
+    fn wrap_at_a_corner_copies_to_all_three_other_corners() {
+        assert_eq!(
+            offsets(BoundaryMode::Wrap, Vector2::new(1., 79.)),
+            vec![
+                Vector2::new(0., 0.),
+                Vector2::new(0., -80.),
+                Vector2::new(100., 0.),
+                Vector2::new(100., -80.),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_in_radius_point_at_the_areas() {
+        let mut grid_system = GridSystem::new(100, 80, 10, BoundaryMode::Reflect);
+        add_area(&mut grid_system, Vector2::new(55., 40.), false);
+        add_area(&mut grid_system, Vector2::new(90., 40.), false);
+        assert_eq!(
+            grid_system.find_offsets_in_radius(&Vector2::new(50., 40.), 10., |_| true),
+            vec![Vector2::new(5., 0.)]
+        );
+    }
+
+    #[test]
+    fn offsets_in_radius_are_filtered() {
+        let mut grid_system = GridSystem::new(100, 80, 10, BoundaryMode::Reflect);
+        add_area(&mut grid_system, Vector2::new(55., 40.), false);
+        add_area(&mut grid_system, Vector2::new(45., 40.), true);
+        assert_eq!(
+            grid_system
+                .find_offsets_in_radius(&Vector2::new(50., 40.), 10., |area| area.symptomatic),
+            vec![Vector2::new(-5., 0.)]
+        );
+    }
+
+    #[test]
+    fn offsets_in_radius_wrap_across_the_edge() {
+        let mut grid_system = GridSystem::new(100, 80, 10, BoundaryMode::Wrap);
+        add_area(&mut grid_system, Vector2::new(2., 40.), false);
+        assert_eq!(
+            grid_system.find_offsets_in_radius(&Vector2::new(98., 40.), 10., |_| true),
+            vec![Vector2::new(4., 0.)]
+        );
+
+        let mut grid_system = GridSystem::new(100, 80, 10, BoundaryMode::Reflect);
+        add_area(&mut grid_system, Vector2::new(2., 40.), false);
+        assert!(grid_system
+            .find_offsets_in_radius(&Vector2::new(98., 40.), 10., |_| true)
+            .is_empty());
     }
 }
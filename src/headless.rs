@@ -0,0 +1,63 @@
+use crate::heatmap::HeatmapMode;
+use crate::options::Options;
+use crate::recording::Recorder;
+use crate::simulation_app::{SimulationApp, STEP_MS};
+use std::io;
+// runs the simulation without a window, for batch runs and machines without a display
+
+pub fn run(mut simulation: SimulationApp, options: &Options) -> Result<(), io::Error> {
+    let arena_size = simulation.arena_size();
+    let mut recorder = match options.record_path.as_ref() {
+        Some(path) => Some(Recorder::create(
+            path,
+            arena_size.x as usize,
+            arena_size.y as usize,
+        )?),
+        None => None,
+    };
+    println!("seed {}", simulation.seed());
+
+    let result = run_loop(&mut simulation, recorder.as_mut(), options).and_then(|_| {
+        if let Some(path) = options.screenshot_path.as_ref() {
+            simulation.rasterize(HeatmapMode::Off).write_png(path)?;
+        }
+        if let Some(path) = options.svg_path.as_ref() {
+            simulation.export_svg(HeatmapMode::Off).write(path)?;
+        }
+        Ok(())
+    });
+    // the recording and the run's files are finished even when the run failed
+    let finished = match recorder {
+        Some(recorder) => recorder.finish(),
+        None => Ok(()),
+    };
+    simulation.finish();
+
+    let counts = simulation.latest_counts();
+    println!(
+        "time {} ms, susceptible {}, infected {}, recovered {}, dead {}",
+        counts.time_ms, counts.susceptible, counts.infected, counts.recovered, counts.dead
+    );
+    result.and(finished)
+}
+
+fn run_loop(
+    simulation: &mut SimulationApp,
+    mut recorder: Option<&mut Recorder>,
+    options: &Options,
+) -> Result<(), io::Error> {
+    loop {
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture(simulation, HeatmapMode::Off)?;
+        }
+        let counts = simulation.latest_counts();
+        let finished = match options.duration_ms {
+            Some(duration_ms) => counts.time_ms >= duration_ms,
+            None => counts.infected == 0,
+        };
+        if finished {
+            return Ok(());
+        }
+        simulation.update(STEP_MS);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::grid_system::GridSystem;
+use crate::painter::Painter;
+use rgx::core::*;
+use rgx::math::*;
+// colours every grid cell by a per cell value, to show hotspots and debug the grid system
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HeatmapMode {
+    Off,
+    Infected,             // organisms currently contagious in the cell
+    CumulativeInfections, // infections that happened in the cell so far
+}
+
+impl HeatmapMode {
+    pub fn next(&self) -> HeatmapMode {
+        match self {
+            HeatmapMode::Off => HeatmapMode::Infected,
+            HeatmapMode::Infected => HeatmapMode::CumulativeInfections,
+            HeatmapMode::CumulativeInfections => HeatmapMode::Off,
+        }
+    }
+}
+
+// values are indexed by grid id and scaled against the largest one
+pub fn render_heatmap<P: Painter>(painter: &mut P, grid_system: &GridSystem, values: &[usize]) {
+    let max_value = *values.iter().max().unwrap_or(&0);
+    if max_value == 0 {
+        return;
+    }
+
+    for (grid_id, value) in values.iter().enumerate() {
+        if *value == 0 {
+            continue;
+        }
+        let square = grid_system.get_cell_square(grid_id);
+        painter.fill_rect(
+            &square.bottom_left,
+            &square.top_right,
+            heat_color(*value as f32 / max_value as f32),
+        );
+    }
+}
+
+// the cell boundaries of the grid system across an arena of size
+pub fn render_grid_lines<P: Painter>(
+    painter: &mut P,
+    grid_system: &GridSystem,
+    size: Vector2<f32>,
+) {
+    let color = Rgba::new(0.5, 0.5, 0.5, 0.4);
+    let grid_size = grid_system.grid_size();
+    let mut x = grid_size;
+    while x < size.x {
+        painter.line(&Vector2::new(x, 0.), &Vector2::new(x, size.y), 0.5, color);
+        x += grid_size;
+    }
+    let mut y = grid_size;
+    while y < size.y {
+        painter.line(&Vector2::new(0., y), &Vector2::new(size.x, y), 0.5, color);
+        y += grid_size;
+    }
+}
+
+// black body style ramp, from dark red through orange to pale yellow
+fn heat_color(heat: f32) -> Rgba {
+    Rgba::new(
+        (0.3 + heat * 2.).min(1.),
+        (heat * 2. - 0.5).clamp(0., 1.),
+        (heat * 3. - 2.).clamp(0., 0.6),
+        0.35 + heat * 0.4,
+    )
+}
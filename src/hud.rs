@@ -0,0 +1,68 @@
+use crate::simulation_app::SimulationApp;
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT, LINE_ADVANCE};
+use rgx::core::*;
+use rgx::kit::shape2d::{Batch, Fill, Shape};
+use rgx::math::*;
+// heads-up display with live statistics drawn over the organisms
+
+const SCALE: f32 = 2.;
+const MARGIN: f32 = 8.;
+
+pub fn render_hud(
+    batch: &mut Batch,
+    simulation: &SimulationApp,
+    fps: f32,
+    paused: bool,
+    speed: f32,
+    fast_forward: bool,
+) {
+    let config = simulation.config();
+    let counts = simulation.latest_counts();
+    let lines = [
+        format!(
+            "TIME {:.1} S   FPS {:.0}   {}   SPEED {}X{}   SEED {}",
+            counts.time_ms as f32 / 1000.,
+            fps,
+            if paused { "PAUSED" } else { "RUNNING" },
+            speed,
+            if fast_forward { " FAST FORWARD" } else { "" },
+            simulation.seed()
+        ),
+        format!(
+            "S {}   I {} ({} SYMPTOMATIC)   R {}   D {}",
+            counts.susceptible, counts.infected, counts.symptomatic, counts.recovered, counts.dead
+        ),
+        format!(
+            "ORGANISMS {}   IN PLACE {}%   MAX VELOCITY {}",
+            config.num_organisms, config.percent_in_place, config.max_velocity
+        ),
+        format!(
+            "INFECTION {} MS   SYMPTOMS {} MS   FATALITY {}%   ISOLATION {}%",
+            config.infection_lifetime_ms,
+            config.symptom_onset_ms,
+            config.fatality_rate,
+            config.self_isolation.compliance
+        ),
+    ];
+
+    let width = lines
+        .iter()
+        .map(|line| text_width(line, SCALE))
+        .fold(0., f32::max);
+    let height = ((lines.len() - 1) as f32 * LINE_ADVANCE + GLYPH_HEIGHT) * SCALE;
+    batch.add(
+        Shape::rect(
+            Point2::new(MARGIN, MARGIN),
+            Point2::new(MARGIN * 3. + width, MARGIN * 3. + height),
+        )
+        .fill(Fill::Solid(Rgba::new(0.0, 0.0, 0.0, 0.6))),
+    );
+
+    for (index, line) in lines.iter().enumerate() {
+        let position = Vector2::new(
+            MARGIN * 2.,
+            MARGIN * 2. + index as f32 * LINE_ADVANCE * SCALE,
+        );
+        draw_text(batch, line, &position, SCALE, Rgba::new(1.0, 1.0, 1.0, 1.0));
+    }
+}
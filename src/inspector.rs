@@ -0,0 +1,114 @@
+use crate::area::OrganismId;
+use crate::organism::InfectionState;
+use crate::painter::Painter;
+use crate::simulation_app::{SimulationApp, WindowAttributes};
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT, LINE_ADVANCE};
+use rgx::core::*;
+use rgx::kit::shape2d::{Batch, Fill, Shape};
+use rgx::math::*;
+// what clicking an organism does, and a panel in the top right corner describing the inspected one
+
+const SCALE: f32 = 2.;
+const MARGIN: f32 = 8.;
+const HIGHLIGHT_MARGIN: f32 = 4.;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickMode {
+    Infect,
+    Immunize,
+    Inspect,
+}
+
+impl PickMode {
+    pub fn next(&self) -> PickMode {
+        match self {
+            PickMode::Infect => PickMode::Immunize,
+            PickMode::Immunize => PickMode::Inspect,
+            PickMode::Inspect => PickMode::Infect,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PickMode::Infect => "INFECT",
+            PickMode::Immunize => "IMMUNIZE",
+            PickMode::Inspect => "INSPECT",
+        }
+    }
+}
+
+fn state_name(infection_state: InfectionState) -> &'static str {
+    match infection_state {
+        InfectionState::Uninfected => "UNINFECTED",
+        InfectionState::Infected => "INFECTED",
+        InfectionState::Symptomatic => "SYMPTOMATIC",
+        InfectionState::Recovered => "RECOVERED",
+        InfectionState::Dead => "DEAD",
+    }
+}
+
+// outlines the selected organism, drawn with the organisms rather than the panel
+pub fn render_selection<P: Painter>(
+    painter: &mut P,
+    simulation: &SimulationApp,
+    selected: Option<OrganismId>,
+) {
+    if let Some(organism) = selected.and_then(|organism_id| simulation.organism(organism_id)) {
+        let square = organism.square();
+        let margin = Vector2::new(HIGHLIGHT_MARGIN, HIGHLIGHT_MARGIN);
+        painter.stroke_rect(
+            &(square.bottom_left - margin),
+            &(square.top_right + margin),
+            1.0,
+            Rgba::new(1.0, 1.0, 1.0, 1.0),
+        );
+    }
+}
+
+pub fn render_inspector(
+    batch: &mut Batch,
+    simulation: &SimulationApp,
+    window: &WindowAttributes,
+    pick_mode: PickMode,
+    selected: Option<OrganismId>,
+) {
+    let mut lines = vec![format!("CLICK: {}", pick_mode.name())];
+    if let Some(organism) = selected.and_then(|organism_id| simulation.organism(organism_id)) {
+        let organism_id = organism.organism_id();
+        lines.push(format!("ORGANISM {}", organism_id));
+        lines.push(format!("STATE {}", state_name(organism.infection_state())));
+        lines.push(format!("VELOCITY {:.1} PX/S", organism.velocity));
+        match simulation.genealogy().infection_of(organism_id) {
+            Some(event) => {
+                lines.push(format!("INFECTED AT {:.2} S", event.time_ms as f32 / 1000.));
+                lines.push(match event.infector {
+                    Some(infector) => format!("SOURCE ORGANISM {}", infector),
+                    None => String::from("SOURCE SEEDED"),
+                });
+            }
+            None => lines.push(String::from("NEVER INFECTED")),
+        }
+    }
+
+    let width = lines
+        .iter()
+        .map(|line| text_width(line, SCALE))
+        .fold(0., f32::max);
+    let height = ((lines.len() - 1) as f32 * LINE_ADVANCE + GLYPH_HEIGHT) * SCALE;
+    let left = window.width as f32 - width - MARGIN * 3.;
+    batch.add(
+        Shape::rect(
+            Point2::new(left, MARGIN),
+            Point2::new(left + width + MARGIN * 2., MARGIN * 3. + height),
+        )
+        .fill(Fill::Solid(Rgba::new(0.0, 0.0, 0.0, 0.6))),
+    );
+
+    for (index, line) in lines.iter().enumerate() {
+        let position = Vector2::new(
+            left + MARGIN,
+            MARGIN * 2. + index as f32 * LINE_ADVANCE * SCALE,
+        );
+        draw_text(batch, line, &position, SCALE, Rgba::new(1.0, 1.0, 1.0, 1.0));
+    }
+}
@@ -1,40 +1,120 @@
 use chrono::Local;
+use rand::random;
 use rgx::color::Rgba;
 use rgx::core::*;
 use rgx::kit;
+use rgx::kit::shape2d::Batch;
+use rgx::math::*;
 
 use winit::{
-    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
 mod area;
+mod camera;
+mod chart;
+mod config;
+mod contact_network;
+mod curve_export;
+mod density_map;
+mod effects;
+mod genealogy;
 mod grid_system;
+mod headless;
+mod heatmap;
+mod hud;
+mod inspector;
+mod movement;
+mod options;
 mod organism;
+mod painter;
+mod playback;
+mod raster;
+mod recording;
+mod reproduction;
 mod simulation_app;
 mod square;
+mod statistics;
+mod svg;
+mod text;
+mod theme;
+mod tui;
 mod window_box;
-use simulation_app::{SimulationApp, WindowAttributes};
+use camera::Camera;
+use config::SimulationConfig;
+use heatmap::HeatmapMode;
+use inspector::PickMode;
+use options::Options;
+use playback::Playback;
+use recording::Recorder;
+use simulation_app::{render_wall, SimulationApp, WindowAttributes};
+use window_box::Wall;
+
+// shorter drags are taken as stray clicks rather than walls
+const MIN_WALL_LENGTH: f32 = 2.;
+// zoom factor per scrolled line
+const ZOOM_STEP: f32 = 1.1;
+// scrolled pixels that count as one line on touchpads
+const PIXELS_PER_LINE: f32 = 40.;
+// where G records to when no --record path is given
+const DEFAULT_RECORD_PATH: &str = "recording.gif";
 
 fn main() -> Result<(), std::io::Error> {
+    let options = Options::parse(std::env::args().skip(1))?;
+    // an optional scenario file overrides the default parameters
+    let config = match options.scenario_path.as_ref() {
+        Some(path) => SimulationConfig::load(path)?,
+        None => SimulationConfig::default(),
+    };
+    let seed = config.seed.unwrap_or_else(random);
+    let mut simulation = SimulationApp::new(config, seed)?;
+    if options.headless {
+        return headless::run(simulation, &options);
+    }
+    if options.tui {
+        return tui::run(simulation, &options);
+    }
+
     let event_loop = EventLoop::new();
     let window = Window::new(&event_loop).unwrap();
-    window.set_resizable(false);
     window.set_title("Pandemic Simulation");
 
     let mut r = Renderer::new(&window)?;
     let win = window.inner_size();
+    // the world pipeline draws the arena through the camera, the other draws overlays in window pixels
+    let world_pip: kit::shape2d::Pipeline = r.pipeline(Blending::default());
     let pip: kit::shape2d::Pipeline = r.pipeline(Blending::default());
     let mut chain = r.swap_chain(win.width as u32, win.height as u32, PresentMode::default());
 
-    let mut simulation = SimulationApp::new(WindowAttributes {
+    let mut window_attributes = WindowAttributes {
         width: win.width as i32,
         height: win.height as i32,
-    });
+    };
+    let mut window_size = Vector2::new(win.width as f32, win.height as f32);
+    let mut camera = Camera::fit(simulation.arena_size(), window_size);
+    let mut panning = false;
 
     let mut last_time = Local::now().timestamp_millis();
-    let mut continue_simulation = false;
+    let mut playback = Playback::new();
+    let mut show_chart = false;
+    let mut heatmap_mode = HeatmapMode::Off;
+    let mut pick_mode = PickMode::Inspect;
+    let mut selected = None;
+    let mut cursor_position = Vector2::new(0., 0.);
+    let mut erase_walls = false;
+    let mut wall_start = None; // where the current right-button drag began
+    let mut recorder = match options.record_path.as_ref() {
+        Some(path) => Some(start_recording(path, &simulation)?),
+        None => None,
+    };
+    let mut fps = 0.;
+    let mut fps_frames = 0;
+    let mut fps_time = 0;
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent { event, .. } => match event {
@@ -48,15 +128,193 @@ fn main() -> Result<(), std::io::Error> {
                 ..
             } => match key {
                 VirtualKeyCode::Space => {
-                    continue_simulation = true;
+                    playback.running = true;
                 }
                 VirtualKeyCode::P => {
-                    continue_simulation = false;
+                    playback.running = false;
+                }
+                VirtualKeyCode::Period | VirtualKeyCode::Right => {
+                    playback.step(&mut simulation);
+                }
+                VirtualKeyCode::Equals | VirtualKeyCode::Add => {
+                    playback.faster();
+                }
+                VirtualKeyCode::Minus | VirtualKeyCode::Subtract => {
+                    playback.slower();
+                }
+                VirtualKeyCode::F => {
+                    playback.fast_forward = !playback.fast_forward;
+                }
+                VirtualKeyCode::C => {
+                    show_chart = !show_chart;
+                }
+                VirtualKeyCode::H => {
+                    heatmap_mode = heatmap_mode.next();
+                }
+                VirtualKeyCode::M => {
+                    pick_mode = pick_mode.next();
+                }
+                VirtualKeyCode::S => {
+                    let path = format!(
+                        "screenshot_{}_{}.png",
+                        simulation.seed(),
+                        simulation.latest_counts().time_ms
+                    );
+                    match simulation.rasterize(heatmap_mode).write_png(&path) {
+                        Ok(()) => println!("saved {}", path),
+                        Err(error) => eprintln!("failed to write screenshot: {}", error),
+                    }
+                }
+                VirtualKeyCode::V => {
+                    let path = format!(
+                        "snapshot_{}_{}.svg",
+                        simulation.seed(),
+                        simulation.latest_counts().time_ms
+                    );
+                    match simulation.export_svg(heatmap_mode).write(&path) {
+                        Ok(()) => println!("saved {}", path),
+                        Err(error) => eprintln!("failed to write svg snapshot: {}", error),
+                    }
+                }
+                VirtualKeyCode::G => match recorder.take() {
+                    Some(recorder) => finish_recording(recorder),
+                    None => {
+                        let path = options
+                            .record_path
+                            .as_deref()
+                            .unwrap_or(DEFAULT_RECORD_PATH);
+                        match start_recording(path, &simulation) {
+                            Ok(started) => recorder = Some(started),
+                            Err(error) => eprintln!("failed to start recording: {}", error),
+                        }
+                    }
+                },
+                VirtualKeyCode::Z => {
+                    camera = Camera::fit(simulation.arena_size(), window_size);
+                }
+                VirtualKeyCode::W => {
+                    erase_walls = !erase_walls;
+                }
+                VirtualKeyCode::R => {
+                    let config = simulation.config().clone();
+                    let seed = simulation.seed();
+                    if let Err(error) = simulation.reset(config, seed) {
+                        eprintln!("failed to restart: {}", error);
+                    }
+                    selected = None;
+                }
+                VirtualKeyCode::N => {
+                    let config = simulation.config().clone();
+                    if let Err(error) = simulation.reset(config, random()) {
+                        eprintln!("failed to restart: {}", error);
+                    }
+                    selected = None;
+                }
+                VirtualKeyCode::L => {
+                    if let Some(path) = options.scenario_path.as_ref() {
+                        // the current run goes on when the scenario can't be loaded
+                        let reloaded = SimulationConfig::load(path).and_then(|config| {
+                            let seed = config.seed.unwrap_or_else(random);
+                            simulation.reset(config, seed)
+                        });
+                        match reloaded {
+                            Ok(()) => {
+                                selected = None;
+                                camera = Camera::fit(simulation.arena_size(), window_size);
+                            }
+                            Err(error) => eprintln!("failed to reload {}: {}", path, error),
+                        }
+                    }
                 }
                 _ => {}
             },
+            // a minimized window reports a size of zero, keep the old swap chain until it returns
+            WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                chain = r.swap_chain(size.width, size.height, PresentMode::default());
+                window_attributes = WindowAttributes {
+                    width: size.width as i32,
+                    height: size.height as i32,
+                };
+                window_size = Vector2::new(size.width as f32, size.height as f32);
+                camera = Camera::fit(simulation.arena_size(), window_size);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Vector2::new(position.x as f32, position.y as f32);
+                if panning {
+                    camera.pan(&(position - cursor_position));
+                }
+                cursor_position = position;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                camera.zoom_at(&cursor_position, ZOOM_STEP.powf(lines), window_size);
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Middle,
+                ..
+            } => {
+                panning = state == ElementState::Pressed;
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let position = camera.screen_to_world(&cursor_position, window_size);
+                selected = simulation.pick_organism(&position);
+                if let Some(organism_id) = selected {
+                    match pick_mode {
+                        PickMode::Infect => simulation.infect_organism(organism_id),
+                        PickMode::Immunize => simulation.immunize_organism(organism_id),
+                        PickMode::Inspect => {}
+                    }
+                }
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Right,
+                ..
+            } => {
+                let position = camera.screen_to_world(&cursor_position, window_size);
+                match state {
+                    ElementState::Pressed => wall_start = Some(position),
+                    ElementState::Released => {
+                        if let Some(start) = wall_start.take() {
+                            if erase_walls {
+                                simulation.erase_walls(&start, &position);
+                            } else if start.distance(position) >= MIN_WALL_LENGTH {
+                                simulation.add_wall(Wall {
+                                    start,
+                                    end: position,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
             _ => *control_flow = ControlFlow::Poll,
         },
+        Event::LoopDestroyed => {
+            if let Some(path) = options.screenshot_path.as_ref() {
+                if let Err(error) = simulation.rasterize(heatmap_mode).write_png(path) {
+                    eprintln!("failed to write screenshot: {}", error);
+                }
+            }
+            if let Some(path) = options.svg_path.as_ref() {
+                if let Err(error) = simulation.export_svg(heatmap_mode).write(path) {
+                    eprintln!("failed to write svg snapshot: {}", error);
+                }
+            }
+            if let Some(recorder) = recorder.take() {
+                finish_recording(recorder);
+            }
+            simulation.finish();
+        }
         Event::MainEventsCleared => {
             *control_flow = ControlFlow::Wait;
 
@@ -64,24 +322,75 @@ fn main() -> Result<(), std::io::Error> {
             let current_time = Local::now().timestamp_millis();
             let delta_time = current_time - last_time;
             last_time = current_time;
-            if continue_simulation {
-                simulation.update(delta_time);
+            playback.advance(&mut simulation, delta_time);
+
+            if let Some(active) = recorder.as_mut() {
+                if let Err(error) = active.capture(&simulation, heatmap_mode) {
+                    eprintln!("failed to record frame: {}", error);
+                    // keeps the frames recorded so far
+                    if let Some(recorder) = recorder.take() {
+                        finish_recording(recorder);
+                    }
+                }
             }
 
-            let app_batch = simulation.render();
+            fps_frames += 1;
+            fps_time += delta_time;
+            if fps_time >= 1000 {
+                fps = fps_frames as f32 * 1000. / fps_time as f32;
+                fps_frames = 0;
+                fps_time = 0;
+            }
+
+            let mut world_batch = Batch::new();
+            simulation.render(&mut world_batch, heatmap_mode);
+            inspector::render_selection(&mut world_batch, &simulation, selected);
+            if let Some(start) = wall_start {
+                let color = if erase_walls {
+                    Rgba::new(1.0, 0.3, 0.3, 1.0)
+                } else {
+                    Rgba::new(1.0, 1.0, 1.0, 1.0)
+                };
+                let wall = Wall {
+                    start,
+                    end: camera.screen_to_world(&cursor_position, window_size),
+                };
+                render_wall(&mut world_batch, &wall, color);
+            }
+            let world_buffer = world_batch.finish(&r);
+
+            let mut app_batch = Batch::new();
+            if show_chart {
+                chart::render_chart(&mut app_batch, &simulation, &window_attributes);
+            }
+            inspector::render_inspector(
+                &mut app_batch,
+                &simulation,
+                &window_attributes,
+                pick_mode,
+                selected,
+            );
+            hud::render_hud(
+                &mut app_batch,
+                &simulation,
+                fps,
+                !playback.running,
+                playback.speed,
+                playback.fast_forward,
+            );
             let buffer = app_batch.finish(&r);
 
             let out = chain.next();
             let mut frame = r.frame();
 
-            r.update_pipeline(
-                &pip,
-                kit::ortho(out.width, out.height, Default::default()),
-                &mut frame,
-            );
+            let ortho = kit::ortho(out.width, out.height, Default::default());
+            r.update_pipeline(&world_pip, ortho * camera.view(window_size), &mut frame);
+            r.update_pipeline(&pip, ortho, &mut frame);
 
             {
                 let pass = &mut frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &out);
+                pass.set_pipeline(&world_pip);
+                pass.draw_buffer(&world_buffer);
                 pass.set_pipeline(&pip);
                 pass.draw_buffer(&buffer);
             }
@@ -93,3 +402,17 @@ fn main() -> Result<(), std::io::Error> {
         _ => *control_flow = ControlFlow::Poll,
     });
 }
+
+fn start_recording(path: &str, simulation: &SimulationApp) -> Result<Recorder, std::io::Error> {
+    let arena_size = simulation.arena_size();
+    let recorder = Recorder::create(path, arena_size.x as usize, arena_size.y as usize)?;
+    println!("recording to {}", path);
+    Ok(recorder)
+}
+
+fn finish_recording(recorder: Recorder) {
+    match recorder.finish() {
+        Ok(()) => println!("recording saved"),
+        Err(error) => eprintln!("failed to write recording: {}", error),
+    }
+}
@@ -0,0 +1,260 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rgx::math::*;
+use std::f32::consts::PI;
+// pluggable movement models deciding where an organism heads each tick
+
+pub struct Movement {
+    pub direction: Vector2<f32>,
+    pub distance: f32,
+}
+
+pub trait MovementModel {
+    // velocity is in pixels per second, direction is the one the organism currently travels in
+    fn next_move(
+        &mut self,
+        delta_ms: i64,
+        velocity: f32,
+        direction: &Vector2<f32>,
+        rng: &mut StdRng,
+    ) -> Movement;
+}
+
+#[derive(Clone, Copy)]
+pub enum MovementModelKind {
+    // constant velocity, with the direction reset at random intervals up to max_direction_ms
+    DirectionReset {
+        max_direction_ms: i64,
+    },
+    // gaussian displacement every tick, velocity acts as the diffusion scale
+    Brownian,
+    // straight flights with power-law distributed lengths, so rare flights are very long
+    LevyFlight {
+        alpha: f32,
+        min_flight: f32,
+        max_flight: f32,
+    },
+    // constant velocity with small gaussian turns, turning_rate is in radians per sqrt(second)
+    CorrelatedRandomWalk {
+        turning_rate: f32,
+    },
+}
+
+impl MovementModelKind {
+    pub fn create(&self, rng: &mut StdRng) -> Box<dyn MovementModel> {
+        match *self {
+            MovementModelKind::DirectionReset { max_direction_ms } => Box::new(DirectionReset {
+                direction_change_ms: 0,
+                max_direction_ms: (max_direction_ms as f32 * rng.gen::<f32>()) as i64,
+            }),
+            MovementModelKind::Brownian => Box::new(Brownian {}),
+            MovementModelKind::LevyFlight {
+                alpha,
+                min_flight,
+                max_flight,
+            } => Box::new(LevyFlight {
+                alpha,
+                min_flight,
+                max_flight,
+                remaining_flight: 0.,
+            }),
+            MovementModelKind::CorrelatedRandomWalk { turning_rate } => {
+                Box::new(CorrelatedRandomWalk { turning_rate })
+            }
+        }
+    }
+}
+
+// a turn is cut off at this many standard deviations, so a single tick never turns an organism
+// around
+const MAX_TURN_DEVIATIONS: f32 = 3.;
+
+pub fn random_direction<R: Rng>(rng: &mut R) -> Vector2<f32> {
+    let angle = 2. * PI * rng.gen::<f32>();
+    Vector2::new(angle.cos(), angle.sin())
+}
+
+// standard normal sample through the Box-Muller transform
+fn standard_normal<R: Rng>(rng: &mut R) -> f32 {
+    let u1 = 1. - rng.gen::<f32>(); // (0, 1], keeps ln away from zero
+    let u2 = rng.gen::<f32>();
+    (-2. * u1.ln()).sqrt() * (2. * PI * u2).cos()
+}
+
+pub struct DirectionReset {
+    direction_change_ms: i64,
+    max_direction_ms: i64,
+}
+
+impl MovementModel for DirectionReset {
+    fn next_move(
+        &mut self,
+        delta_ms: i64,
+        velocity: f32,
+        direction: &Vector2<f32>,
+        rng: &mut StdRng,
+    ) -> Movement {
+        let mut direction = *direction;
+        if self.direction_change_ms > self.max_direction_ms {
+            self.direction_change_ms = 0;
+            direction = random_direction(rng);
+        }
+        self.direction_change_ms += delta_ms;
+
+        Movement {
+            direction,
+            distance: velocity * (delta_ms as f32) / 1000.0,
+        }
+    }
+}
+
+pub struct Brownian {}
+
+impl MovementModel for Brownian {
+    fn next_move(
+        &mut self,
+        delta_ms: i64,
+        velocity: f32,
+        direction: &Vector2<f32>,
+        rng: &mut StdRng,
+    ) -> Movement {
+        let scale = velocity * ((delta_ms as f32) / 1000.0).sqrt();
+        let shift = Vector2::new(scale * standard_normal(rng), scale * standard_normal(rng));
+        let distance = shift.magnitude();
+        if distance == 0. {
+            return Movement {
+                direction: *direction,
+                distance,
+            };
+        }
+        Movement {
+            direction: shift * (1. / distance),
+            distance,
+        }
+    }
+}
+
+pub struct LevyFlight {
+    alpha: f32,
+    min_flight: f32,
+    max_flight: f32,
+    remaining_flight: f32,
+}
+
+impl MovementModel for LevyFlight {
+    fn next_move(
+        &mut self,
+        delta_ms: i64,
+        velocity: f32,
+        direction: &Vector2<f32>,
+        rng: &mut StdRng,
+    ) -> Movement {
+        let mut direction = *direction;
+        if self.remaining_flight <= 0. {
+            // pareto distributed flight length
+            let u = 1. - rng.gen::<f32>();
+            self.remaining_flight =
+                (self.min_flight * u.powf(-1. / self.alpha)).min(self.max_flight);
+            direction = random_direction(rng);
+        }
+
+        let distance = (velocity * (delta_ms as f32) / 1000.0).min(self.remaining_flight);
+        self.remaining_flight -= distance;
+        Movement {
+            direction,
+            distance,
+        }
+    }
+}
+
+pub struct CorrelatedRandomWalk {
+    turning_rate: f32,
+}
+
+impl MovementModel for CorrelatedRandomWalk {
+    fn next_move(
+        &mut self,
+        delta_ms: i64,
+        velocity: f32,
+        direction: &Vector2<f32>,
+        rng: &mut StdRng,
+    ) -> Movement {
+        let delta_s = (delta_ms as f32) / 1000.0;
+        let turn = self.turning_rate
+            * delta_s.sqrt()
+            * standard_normal(rng).clamp(-MAX_TURN_DEVIATIONS, MAX_TURN_DEVIATIONS);
+        let (sin, cos) = turn.sin_cos();
+        Movement {
+            direction: Vector2::new(
+                direction.x * cos - direction.y * sin,
+                direction.x * sin + direction.y * cos,
+            ),
+            distance: velocity * delta_s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK_MS: i64 = 16;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(7)
+    }
+
+    // the angle between two unit directions
+    fn angle(first: &Vector2<f32>, second: &Vector2<f32>) -> f32 {
+        Vector2::dot(*first, *second).clamp(-1., 1.).acos()
+    }
+
+    #[test]
+    fn levy_flights_stay_within_bounds() {
+        let mut rng = rng();
+        let mut model = MovementModelKind::LevyFlight {
+            alpha: 0.5,
+            min_flight: 5.,
+            max_flight: 200.,
+        }
+        .create(&mut rng);
+        let direction = Vector2::new(1., 0.);
+        for _ in 0..1000 {
+            // fast enough to cover every flight in a single tick
+            let step = model.next_move(TICK_MS, 1_000_000., &direction, &mut rng);
+            assert!(
+                step.distance >= 5. && step.distance <= 200.,
+                "{}",
+                step.distance
+            );
+        }
+    }
+
+    #[test]
+    fn brownian_direction_changes_every_tick() {
+        let mut rng = rng();
+        let mut model = MovementModelKind::Brownian.create(&mut rng);
+        let mut direction = Vector2::new(1., 0.);
+        for _ in 0..100 {
+            let step = model.next_move(TICK_MS, 50., &direction, &mut rng);
+            assert!(step.distance > 0.);
+            assert!(step.direction != direction);
+            direction = step.direction;
+        }
+    }
+
+    #[test]
+    fn correlated_walk_turns_are_bounded() {
+        let turning_rate = 2.;
+        let max_turn = turning_rate * (TICK_MS as f32 / 1000.).sqrt() * MAX_TURN_DEVIATIONS;
+        let mut rng = rng();
+        let mut model = MovementModelKind::CorrelatedRandomWalk { turning_rate }.create(&mut rng);
+        let mut direction = Vector2::new(1., 0.);
+        for _ in 0..10_000 {
+            let step = model.next_move(TICK_MS, 50., &direction, &mut rng);
+            assert!(angle(&direction, &step.direction) <= max_turn + 1e-3);
+            assert_eq!(step.distance, 50. * TICK_MS as f32 / 1000.);
+            direction = step.direction;
+        }
+    }
+}
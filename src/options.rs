@@ -0,0 +1,115 @@
+use std::io;
+// command line options:
+// pandemic_simulation [scenario] [--headless | --tui] [--duration-ms N] [--screenshot PATH] [--svg PATH]
+//                     [--record PATH]
+
+#[derive(Default)]
+pub struct Options {
+    pub scenario_path: Option<String>,
+    pub headless: bool,           // runs without a window, as fast as possible
+    pub tui: bool,                // draws into the terminal instead of a window
+    pub duration_ms: Option<i64>, // simulated time a headless run stops at, None runs until the outbreak ends
+    pub screenshot_path: Option<String>, // PNG of the arena written when the run ends
+    pub svg_path: Option<String>, // SVG of the arena written when the run ends
+    pub record_path: Option<String>, // GIF or APNG recorded from the start
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, io::Error> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--tui" => options.tui = true,
+                "--duration-ms" => {
+                    let value = value_of(&arg, args.next())?;
+                    options.duration_ms =
+                        Some(value.parse().map_err(|_| {
+                            invalid(format!("invalid value for {}: {}", arg, value))
+                        })?);
+                }
+                "--screenshot" => options.screenshot_path = Some(value_of(&arg, args.next())?),
+                "--svg" => options.svg_path = Some(value_of(&arg, args.next())?),
+                "--record" => options.record_path = Some(value_of(&arg, args.next())?),
+                _ if arg.starts_with("--") => {
+                    return Err(invalid(format!("unknown option {}", arg)));
+                }
+                _ if options.scenario_path.is_none() => options.scenario_path = Some(arg),
+                _ => return Err(invalid(format!("unexpected argument {}", arg))),
+            }
+        }
+        if options.headless && options.tui {
+            return Err(invalid(
+                "--headless and --tui can't be combined".to_string(),
+            ));
+        }
+        // the terminal front end doesn't record, rather than silently ignoring the path
+        if options.tui && options.record_path.is_some() {
+            return Err(invalid("--record isn't supported with --tui".to_string()));
+        }
+        Ok(options)
+    }
+}
+
+fn value_of(option: &str, value: Option<String>) -> Result<String, io::Error> {
+    value.ok_or_else(|| invalid(format!("{} needs a value", option)))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, io::Error> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn options_and_scenario_in_any_order() {
+        let options = parse(&[
+            "--headless",
+            "scenario.txt",
+            "--duration-ms",
+            "5000",
+            "--screenshot",
+            "end.png",
+            "--record",
+            "run.gif",
+        ])
+        .unwrap();
+        assert_eq!(options.scenario_path.as_deref(), Some("scenario.txt"));
+        assert!(options.headless);
+        assert!(!options.tui);
+        assert_eq!(options.duration_ms, Some(5000));
+        assert_eq!(options.screenshot_path.as_deref(), Some("end.png"));
+        assert_eq!(options.svg_path, None);
+        assert_eq!(options.record_path.as_deref(), Some("run.gif"));
+    }
+
+    #[test]
+    fn no_arguments_keep_the_defaults() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.scenario_path, None);
+        assert!(!options.headless);
+        assert_eq!(options.duration_ms, None);
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        for args in &[
+            &["--fast"][..],
+            &["--duration-ms"][..],
+            &["--duration-ms", "soon"][..],
+            &["--svg"][..],
+            &["first.txt", "second.txt"][..],
+            &["--headless", "--tui"][..],
+            &["--tui", "--record", "out.gif"][..],
+        ] {
+            let error = parse(args).err().expect("an error");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
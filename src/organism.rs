@@ -1,73 +1,110 @@
-use crate::area::{Area, AreaPtr};
+use crate::area::{Area, AreaPtr, OrganismId};
+use crate::config::SimulationConfig;
+use crate::density_map::DensityMap;
 use crate::grid_system::GridSystem;
+use crate::movement::{random_direction, MovementModel};
+use crate::painter::Painter;
+use crate::square::Square;
+use crate::theme::Theme;
 use crate::window_box::WindowBox;
 use rand::prelude::*;
+use rand::rngs::StdRng;
 use rgx::core::*;
-use rgx::kit::shape2d::{Batch, Fill, Shape};
 use rgx::math::*;
-use std::f32::consts::PI;
 
-#[derive(PartialEq)]
-enum InfectionState {
+const ISOLATION_MARGIN: f32 = 1.5;
+const ISOLATION_COLOR: Rgba = Rgba::new(1.0, 1.0, 1.0, 0.8);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum InfectionState {
     Uninfected,
     Infected,
+    Symptomatic,
     Recovered,
     Dead,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum OrganismShape {
+    Square,
+    Circle,
+}
+
+// how organisms are drawn, which leaves the squares they collide with untouched
+#[derive(Clone, Copy)]
+pub struct OrganismStyle {
+    pub shape: OrganismShape,
+    pub size_by_velocity: bool, // from half the size when in place to one and a half at max_velocity
+    pub outline_isolating: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct SelfIsolation {
+    pub compliance: f32,      // percentage of symptomatic organisms that isolate
+    pub velocity_factor: f32, // applied to an isolating organism's velocity, 0 stops it entirely
+}
+
+#[derive(Clone, Copy)]
+pub struct FearResponse {
+    pub perception_radius: f32, // in pixels
+    pub symptomatic_only: bool, // only symptomatic organisms are visibly infected
+    pub sensitivity: f32,       // steering weight is sensitivity * prevalence, capped at 1
+}
+
 pub struct OrganismState {
     pub position: Vector2<f32>,
     area: AreaPtr,
     pub velocity: f32,
+    base_velocity: f32, // velocity to resume once an isolating organism recovers
     direction: Vector2<f32>,
-    direction_change_ms: i64,
-    max_direction_ms: i64,
+    movement: Box<dyn MovementModel>,
     infection_time: i64,
     infection_lifetime_ms: i64,
+    symptom_onset_ms: i64,
+    self_isolation: SelfIsolation,
     fatality_rate: f32,
     infection_state: InfectionState,
+    isolating: bool,
 }
 
 impl OrganismState {
     pub fn random(
-        width: f32,
-        height: f32,
-        size: f32,
-        max_velocity: f32,
-        percentage_in_place: f32,
-        infection_lifetime_ms: i64,
-        fatality_rate: f32,
+        config: &SimulationConfig,
+        density_map: Option<&DensityMap>,
+        organism_id: OrganismId,
         grid_system: &GridSystem,
+        rng: &mut StdRng,
     ) -> Self {
-        let mut rng = rand::thread_rng();
-        let x = width * rng.gen::<f32>();
-        let y = height * rng.gen::<f32>();
-        let max_direction_ms = (7000. * rng.gen::<f32>()) as i64;
+        let width = config.arena_width as f32;
+        let height = config.arena_height as f32;
+        let position = match density_map {
+            Some(density_map) => density_map.sample(width, height, rng),
+            None => Vector2::new(width * rng.gen::<f32>(), height * rng.gen::<f32>()),
+        };
 
-        let velocity = if rng.gen::<f32>() * 100. < percentage_in_place {
+        let velocity = if rng.gen::<f32>() * 100. < config.percent_in_place {
             0.
         } else {
-            max_velocity * rng.gen::<f32>()
+            config.max_velocity * rng.gen::<f32>()
         };
 
-        let angle = 2. * PI * rng.gen::<f32>();
-        let ang_x = angle.cos();
-        let ang_y = angle.sin();
-        let direction = Vector2::new(ang_x, ang_y);
+        let direction = random_direction(rng);
 
-        let position = Vector2::new(x, y);
         let grid_id = grid_system.get_grid_index(&position);
         Self {
             position,
-            area: Area::new(&position, grid_id, size),
+            area: Area::new(&position, grid_id, organism_id, config.organism_size),
             velocity,
-            direction_change_ms: 0,
-            max_direction_ms,
+            base_velocity: velocity,
+            movement: config.movement_model.create(rng),
             infection_time: 0,
-            infection_lifetime_ms,
-            fatality_rate,
+            infection_lifetime_ms: config.infection_lifetime_ms,
+            symptom_onset_ms: config.symptom_onset_ms,
+            self_isolation: config.self_isolation,
+            fatality_rate: config.fatality_rate,
             direction,
             infection_state: InfectionState::Uninfected,
+            isolating: false,
         }
     }
 
@@ -81,84 +118,270 @@ impl OrganismState {
         }
     }
 
-    pub fn update(&mut self, delta_ms: i64, window_box: &WindowBox) {
-        let shift = self.velocity * (delta_ms as f32) / 1000.0;
-        let result = window_box.collided_velocity(&self.position, shift, &self.direction);
-        self.position = result.position;
-        self.direction = result.direction;
-        (&*self.area).borrow_mut().square.update(&self.position);
+    // makes an uninfected organism immune, as if it had recovered
+    pub fn immunize(&mut self) {
+        if self.infection_state == InfectionState::Uninfected {
+            self.infection_state = InfectionState::Recovered;
+        }
+    }
 
-        if self.direction_change_ms > self.max_direction_ms {
-            let mut rng = rand::thread_rng();
-            self.direction_change_ms = 0;
-            let angle = 2. * PI * rng.gen::<f32>();
-            let ang_x = angle.cos();
-            let ang_y = angle.sin();
-            self.direction = Vector2::new(ang_x, ang_y);
+    pub fn organism_id(&self) -> OrganismId {
+        self.area.borrow().organism_id
+    }
+
+    pub fn infection_state(&self) -> InfectionState {
+        self.infection_state
+    }
+
+    pub fn square(&self) -> Square {
+        self.area.borrow().square.clone()
+    }
+
+    pub fn is_contagious(&self) -> bool {
+        self.infection_state == InfectionState::Infected
+            || self.infection_state == InfectionState::Symptomatic
+    }
+
+    fn set_symptomatic(&mut self, rng: &mut StdRng) {
+        self.infection_state = InfectionState::Symptomatic;
+        self.area.borrow_mut().symptomatic = true;
+        if rng.gen::<f32>() * 100. < self.self_isolation.compliance {
+            self.velocity = self.base_velocity * self.self_isolation.velocity_factor;
+            self.isolating = true;
         }
+    }
+
+    // steers away from visibly infected neighbours, closer ones pushing harder
+    fn avoid_infected(
+        &self,
+        direction: &Vector2<f32>,
+        grid_system: &GridSystem,
+        fear_response: &FearResponse,
+        prevalence: f32,
+    ) -> Vector2<f32> {
+        let offsets = grid_system.find_offsets_in_radius(
+            &self.position,
+            fear_response.perception_radius,
+            |area| !fear_response.symptomatic_only || area.symptomatic,
+        );
+
+        let mut away = Vector2::new(0., 0.);
+        for offset in offsets.iter() {
+            let distance_squared = offset.x * offset.x + offset.y * offset.y;
+            if distance_squared > 0. {
+                away = away - *offset * (1. / distance_squared);
+            }
+        }
+        if away.magnitude() == 0. {
+            return *direction;
+        }
+
+        let weight = (fear_response.sensitivity * prevalence).min(1.);
+        let steered = *direction * (1. - weight) + away.normalize() * weight;
+        if steered.magnitude() == 0. {
+            away.normalize()
+        } else {
+            steered.normalize()
+        }
+    }
+
+    // adds the organism to a grid other than the infection grid, leaving its grid_id untouched
+    pub fn add_to_grid(&self, grid_system: &mut GridSystem) {
+        let grid_id = grid_system.get_grid_index(&self.position);
+        grid_system.add_area(&self.area, grid_id);
+    }
 
-        self.direction_change_ms += delta_ms;
+    pub fn update(
+        &mut self,
+        delta_ms: i64,
+        window_box: &WindowBox,
+        grid_system: &GridSystem,
+        fear_response: Option<&FearResponse>,
+        prevalence: f32,
+        rng: &mut StdRng,
+    ) {
+        let mut step = self
+            .movement
+            .next_move(delta_ms, self.velocity, &self.direction, rng);
+        if let Some(fear_response) = fear_response {
+            if self.infection_state == InfectionState::Uninfected && step.distance > 0. {
+                step.direction =
+                    self.avoid_infected(&step.direction, grid_system, fear_response, prevalence);
+            }
+        }
+        let result = window_box.collided_velocity(&self.position, step.distance, &step.direction);
+        self.position = result.position;
+        self.direction = result.direction;
+        (&*self.area).borrow_mut().square.update(&self.position);
     }
 
-    pub fn check_infected(&mut self, delta_time: i64, grid_system: &mut GridSystem) {
+    // returns the organism that infected this one, if that happened during this check
+    pub fn check_infected(
+        &mut self,
+        delta_time: i64,
+        grid_system: &mut GridSystem,
+        rng: &mut StdRng,
+    ) -> Option<OrganismId> {
         let old_grid_id = (&*self.area).borrow().grid_id;
         let new_grid_id = grid_system.get_grid_index(&self.position);
+        let mut infector = None;
 
         if self.infection_state == InfectionState::Uninfected {
-            let grid_ids =
-                grid_system.get_grid_id_list(&(&*self.area).borrow().square.add_half_size_bias());
-            'outer: for grid_id in grid_ids.iter() {
-                if grid_system.find_intersection_in_grid(*grid_id, &(&*self.area).borrow().square) {
-                    self.infection_state = InfectionState::Infected;
-                    break 'outer;
+            let squares = grid_system.get_wrapped_squares(&(&*self.area).borrow().square);
+            'outer: for square in squares.iter() {
+                let grid_ids = grid_system.get_grid_id_list(&square.add_half_size_bias());
+                for grid_id in grid_ids.iter() {
+                    if let Some(source) = grid_system.find_intersection_in_grid(*grid_id, square) {
+                        self.infection_state = InfectionState::Infected;
+                        infector = Some(source);
+                        break 'outer;
+                    }
                 }
             }
-        } else if self.infection_state == InfectionState::Infected {
+        } else if self.is_contagious() {
             self.infection_time += delta_time;
+            if self.infection_state == InfectionState::Infected
+                && self.infection_time >= self.symptom_onset_ms
+            {
+                self.set_symptomatic(rng);
+            }
             if self.infection_time >= self.infection_lifetime_ms {
-                let mut rng = rand::thread_rng();
                 grid_system.remove_area_from_grid((&*self.area).borrow().area_id, old_grid_id);
                 if rng.gen::<f32>() * 100. < self.fatality_rate {
                     self.infection_state = InfectionState::Dead;
                     self.velocity = 0.0;
                 } else {
                     self.infection_state = InfectionState::Recovered;
+                    self.velocity = self.base_velocity;
                 }
+                self.isolating = false;
                 self.infection_time = 0;
             }
         }
 
-        if self.infection_state == InfectionState::Infected {
-            if old_grid_id != new_grid_id {
-                grid_system.remove_area_from_grid((&*self.area).borrow().area_id, old_grid_id);
-                grid_system.add_area(&self.area, new_grid_id);
-                let mut area = (&*self.area).borrow_mut();
-                area.grid_id = new_grid_id;
-            }
+        if self.is_contagious() && old_grid_id != new_grid_id {
+            grid_system.remove_area_from_grid((&*self.area).borrow().area_id, old_grid_id);
+            grid_system.add_area(&self.area, new_grid_id);
+            let mut area = (&*self.area).borrow_mut();
+            area.grid_id = new_grid_id;
         }
+        infector
     }
 
-    pub fn render(&self, batch: &mut Batch, frame: u32) {
-        let color = match self.infection_state {
-            InfectionState::Uninfected => Rgba::new(0.0, 0.5, 0.0, 1.0),
-            InfectionState::Infected => Rgba::new(1.0, 0.0, 0.0, 1.0),
-            InfectionState::Recovered => Rgba::new(0.25, 0.25, 0.25, 1.0),
-            InfectionState::Dead => {
-                if frame >> 3 & 0x1 == 1 {
-                    Rgba::new(1.0, 0., 1.0, 1.0)
-                } else {
-                    Rgba::new(0.0, 0., 0., 1.0)
-                }
+    pub fn render<P: Painter>(
+        &self,
+        painter: &mut P,
+        theme: &Theme,
+        style: &OrganismStyle,
+        max_velocity: f32,
+        frame: u32,
+    ) {
+        // dead organisms blink
+        let color = if self.infection_state == InfectionState::Dead
+            && theme.blink_dead
+            && frame >> 3 & 0x1 == 0
+        {
+            Rgba::new(0.0, 0., 0., 1.0)
+        } else {
+            theme.color(self.infection_state)
+        };
+        let square = &self.area.borrow().square;
+        // squares have always had a one pixel outline around them, circles are as large
+        let mut radius = (square.top_right.x - square.bottom_left.x + 1.) * 0.5;
+        if style.size_by_velocity && max_velocity > 0. {
+            radius *= 0.5 + (self.velocity / max_velocity).min(1.);
+        }
+        let half_size = Vector2::new(radius - 0.5, radius - 0.5);
+        match style.shape {
+            OrganismShape::Square => {
+                let bottom_left = square.center - half_size;
+                let top_right = square.center + half_size;
+                painter.fill_rect(&bottom_left, &top_right, color);
+                painter.stroke_rect(&bottom_left, &top_right, 1.0, color);
             }
+            OrganismShape::Circle => painter.fill_circle(&square.center, radius, color),
+        }
+
+        if style.outline_isolating && self.isolating {
+            let margin = Vector2::new(ISOLATION_MARGIN, ISOLATION_MARGIN);
+            match style.shape {
+                OrganismShape::Square => painter.stroke_rect(
+                    &(square.center - half_size - margin),
+                    &(square.center + half_size + margin),
+                    1.0,
+                    ISOLATION_COLOR,
+                ),
+                OrganismShape::Circle => painter.stroke_circle(
+                    &square.center,
+                    radius + ISOLATION_MARGIN,
+                    1.0,
+                    ISOLATION_COLOR,
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_box::BoundaryMode;
+
+    const FEAR_RESPONSE: FearResponse = FearResponse {
+        perception_radius: 30.,
+        symptomatic_only: true,
+        sensitivity: 10.,
+    };
+
+    fn grid_system() -> GridSystem {
+        GridSystem::new(200, 200, 25, BoundaryMode::Reflect)
+    }
+
+    fn organism_at(position: Vector2<f32>, grid_system: &GridSystem) -> OrganismState {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut organism =
+            OrganismState::random(&SimulationConfig::default(), None, 0, grid_system, &mut rng);
+        organism.position = position;
+        organism
+    }
+
+    fn add_infected(grid_system: &mut GridSystem, position: Vector2<f32>, symptomatic: bool) {
+        let grid_id = grid_system.get_grid_index(&position);
+        let area = Area::new(&position, grid_id, 1, 3.);
+        area.borrow_mut().symptomatic = symptomatic;
+        grid_system.add_area(&area, grid_id);
+    }
+
+    #[test]
+    fn organisms_steer_away_from_the_infected() {
+        let mut grid_system = grid_system();
+        add_infected(&mut grid_system, Vector2::new(110., 100.), true);
+        let organism = organism_at(Vector2::new(100., 100.), &grid_system);
+        let direction = Vector2::new(0., 1.);
+
+        let steered = organism.avoid_infected(&direction, &grid_system, &FEAR_RESPONSE, 1.);
+        assert!(steered.x < 0.);
+
+        // a lower prevalence only bends the direction
+        let steered = organism.avoid_infected(&direction, &grid_system, &FEAR_RESPONSE, 0.05);
+        assert!(steered.x < 0. && steered.y > 0.);
+    }
+
+    #[test]
+    fn asymptomatic_carriers_go_unnoticed() {
+        let mut grid_system = grid_system();
+        add_infected(&mut grid_system, Vector2::new(110., 100.), false);
+        let organism = organism_at(Vector2::new(100., 100.), &grid_system);
+        let direction = Vector2::new(0., 1.);
+
+        let steered = organism.avoid_infected(&direction, &grid_system, &FEAR_RESPONSE, 1.);
+        assert_eq!(steered, direction);
+
+        let fear_response = FearResponse {
+            symptomatic_only: false,
+            ..FEAR_RESPONSE
         };
-        let square = &(&*self.area).borrow().square;
-        batch.add(
-            Shape::rect(
-                Point2::new(square.bottom_left.x, square.bottom_left.y),
-                Point2::new(square.top_right.x, square.top_right.y),
-            )
-            .fill(Fill::Solid(color.clone()))
-            .stroke(1.0, color.clone()),
-        );
+        let steered = organism.avoid_infected(&direction, &grid_system, &fear_response, 1.);
+        assert!(steered.x < 0.);
     }
 }
@@ -0,0 +1,106 @@
+use rgx::core::*;
+use rgx::kit::shape2d::{Batch, Fill, Shape};
+use rgx::math::*;
+// the few drawing operations the arena needs, so it can be drawn to the screen or elsewhere
+
+const MIN_CIRCLE_SIDES: u32 = 8;
+const MAX_CIRCLE_SIDES: u32 = 32;
+
+pub trait Painter {
+    fn fill_rect(&mut self, bottom_left: &Vector2<f32>, top_right: &Vector2<f32>, color: Rgba);
+    // the outline is centered on the rectangle's edges
+    fn stroke_rect(
+        &mut self,
+        bottom_left: &Vector2<f32>,
+        top_right: &Vector2<f32>,
+        width: f32,
+        color: Rgba,
+    );
+    fn line(&mut self, start: &Vector2<f32>, end: &Vector2<f32>, width: f32, color: Rgba);
+    fn fill_circle(&mut self, center: &Vector2<f32>, radius: f32, color: Rgba);
+    // the outline is centered on the circle
+    fn stroke_circle(&mut self, center: &Vector2<f32>, radius: f32, width: f32, color: Rgba);
+}
+
+impl Painter for Batch {
+    fn fill_rect(&mut self, bottom_left: &Vector2<f32>, top_right: &Vector2<f32>, color: Rgba) {
+        self.add(
+            Shape::rect(
+                Point2::new(bottom_left.x, bottom_left.y),
+                Point2::new(top_right.x, top_right.y),
+            )
+            .fill(Fill::Solid(color))
+            // shape2d otherwise adds an invisible stroke, with vertices of its own
+            .stroke(0., Rgba::TRANSPARENT),
+        );
+    }
+
+    fn stroke_rect(
+        &mut self,
+        bottom_left: &Vector2<f32>,
+        top_right: &Vector2<f32>,
+        width: f32,
+        color: Rgba,
+    ) {
+        self.add(
+            Shape::rect(
+                Point2::new(bottom_left.x, bottom_left.y),
+                Point2::new(top_right.x, top_right.y),
+            )
+            .stroke(width, color),
+        );
+    }
+
+    fn line(&mut self, start: &Vector2<f32>, end: &Vector2<f32>, width: f32, color: Rgba) {
+        self.add(
+            Shape::line(Point2::new(start.x, start.y), Point2::new(end.x, end.y))
+                .stroke(width, color),
+        );
+    }
+
+    fn fill_circle(&mut self, center: &Vector2<f32>, radius: f32, color: Rgba) {
+        self.add(
+            Shape::circle(
+                Point2::new(center.x, center.y),
+                radius,
+                circle_sides(radius),
+            )
+            .fill(Fill::Solid(color))
+            .stroke(0., Rgba::TRANSPARENT),
+        );
+    }
+
+    fn stroke_circle(&mut self, center: &Vector2<f32>, radius: f32, width: f32, color: Rgba) {
+        // shape2d strokes circles on the inside
+        let outer_radius = radius + width * 0.5;
+        self.add(
+            Shape::circle(
+                Point2::new(center.x, center.y),
+                outer_radius,
+                circle_sides(outer_radius),
+            )
+            .stroke(width, color),
+        );
+    }
+}
+
+// small circles, like organisms, get by with few sides, which keeps large populations cheap
+fn circle_sides(radius: f32) -> u32 {
+    ((radius * 4.).ceil() as u32).clamp(MIN_CIRCLE_SIDES, MAX_CIRCLE_SIDES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_sides_grow_with_the_radius() {
+        assert_eq!(circle_sides(0.), MIN_CIRCLE_SIDES);
+        // the default organism size
+        assert_eq!(circle_sides(2.), MIN_CIRCLE_SIDES);
+        assert_eq!(circle_sides(2.1), 9);
+        assert_eq!(circle_sides(5.), 20);
+        assert_eq!(circle_sides(8.), MAX_CIRCLE_SIDES);
+        assert_eq!(circle_sides(100.), MAX_CIRCLE_SIDES);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::simulation_app::{SimulationApp, STEP_MS};
+// how real time turns into simulation ticks, shared by the window and the terminal front ends
+
+// how many times faster than the speed fast-forward runs
+const FAST_FORWARD_FACTOR: f32 = 10.;
+// time that doesn't fit in this many ticks per frame is dropped rather than piling up
+const MAX_TICKS_PER_FRAME: usize = 200;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 16.;
+
+pub struct Playback {
+    pub running: bool,
+    pub speed: f32,
+    pub fast_forward: bool,
+    pending_ms: f32, // simulated time not yet run in ticks
+}
+
+impl Playback {
+    // starts paused
+    pub fn new() -> Playback {
+        Playback {
+            running: false,
+            speed: 1.,
+            fast_forward: false,
+            pending_ms: 0.,
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed * 0.5).max(MIN_SPEED);
+    }
+
+    // a single tick, only while paused
+    pub fn step(&self, simulation: &mut SimulationApp) {
+        if !self.running {
+            simulation.update(STEP_MS);
+        }
+    }
+
+    // runs as many ticks as fit in delta_ms of real time
+    pub fn advance(&mut self, simulation: &mut SimulationApp, delta_ms: i64) {
+        if !self.running {
+            return;
+        }
+        let factor = if self.fast_forward {
+            FAST_FORWARD_FACTOR
+        } else {
+            1.
+        };
+        self.pending_ms += delta_ms as f32 * self.speed * factor;
+        let mut ticks = 0;
+        while self.pending_ms >= STEP_MS as f32 && ticks < MAX_TICKS_PER_FRAME {
+            simulation.update(STEP_MS);
+            self.pending_ms -= STEP_MS as f32;
+            ticks += 1;
+        }
+        if ticks == MAX_TICKS_PER_FRAME {
+            self.pending_ms = 0.;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SimulationConfig;
+
+    fn simulation() -> SimulationApp {
+        let config = SimulationConfig {
+            num_organisms: 20,
+            num_initially_infected: 2,
+            ..SimulationConfig::default()
+        };
+        SimulationApp::new(config, 1).unwrap()
+    }
+
+    fn ticks(simulation: &SimulationApp) -> i64 {
+        simulation.latest_counts().time_ms / STEP_MS
+    }
+
+    #[test]
+    fn single_steps_only_while_paused() {
+        let mut simulation = simulation();
+        let mut playback = Playback::new();
+        playback.step(&mut simulation);
+        assert_eq!(ticks(&simulation), 1);
+
+        playback.running = true;
+        playback.step(&mut simulation);
+        assert_eq!(ticks(&simulation), 1);
+    }
+
+    #[test]
+    fn nothing_runs_while_paused() {
+        let mut simulation = simulation();
+        let mut playback = Playback::new();
+        playback.advance(&mut simulation, 1000);
+        assert_eq!(ticks(&simulation), 0);
+    }
+
+    #[test]
+    fn speed_scales_the_ticks() {
+        let mut simulation = simulation();
+        let mut playback = Playback::new();
+        playback.running = true;
+        playback.advance(&mut simulation, STEP_MS * 4);
+        assert_eq!(ticks(&simulation), 4);
+
+        playback.faster();
+        playback.advance(&mut simulation, STEP_MS * 4);
+        assert_eq!(ticks(&simulation), 12);
+
+        playback.slower();
+        playback.slower();
+        // half a tick is kept for the next frame
+        playback.advance(&mut simulation, STEP_MS * 3);
+        assert_eq!(ticks(&simulation), 13);
+        playback.advance(&mut simulation, STEP_MS);
+        assert_eq!(ticks(&simulation), 14);
+
+        playback.speed = 1.;
+        playback.fast_forward = true;
+        playback.advance(&mut simulation, STEP_MS);
+        assert_eq!(ticks(&simulation), 24);
+    }
+
+    #[test]
+    fn a_long_stall_is_capped() {
+        let mut simulation = simulation();
+        let mut playback = Playback::new();
+        playback.running = true;
+        playback.advance(&mut simulation, 60_000);
+        assert_eq!(ticks(&simulation), MAX_TICKS_PER_FRAME as i64);
+
+        // the rest of the stall was dropped
+        playback.advance(&mut simulation, STEP_MS);
+        assert_eq!(ticks(&simulation), MAX_TICKS_PER_FRAME as i64 + 1);
+    }
+
+    #[test]
+    fn speed_stays_within_bounds() {
+        let mut playback = Playback::new();
+        for _ in 0..10 {
+            playback.faster();
+        }
+        assert_eq!(playback.speed, MAX_SPEED);
+        for _ in 0..20 {
+            playback.slower();
+        }
+        assert_eq!(playback.speed, MIN_SPEED);
+    }
+}
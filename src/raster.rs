@@ -0,0 +1,178 @@
+use crate::painter::Painter;
+use rgx::core::*;
+use rgx::math::*;
+use std::fs;
+use std::io;
+use std::io::Write;
+// a small software rasterizer, so frames can be saved without a window or a GPU
+
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>, // RGBA, row by row from the top
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: Rgba) -> Canvas {
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for _ in 0..width * height {
+            pixels.extend_from_slice(&to_bytes(background));
+        }
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn write_png(&self, path: &str) -> Result<(), io::Error> {
+        // encoded in memory first, as the encoder only finishes the file when dropped
+        let mut png = vec![];
+        self.encode_png(&mut png)?;
+        fs::write(path, png)
+    }
+
+    pub fn encode_png<W: Write>(&self, writer: W) -> Result<(), io::Error> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    // a pixel is covered when its center is inside the rectangle
+    fn blend_rect(&mut self, left: f32, top: f32, right: f32, bottom: f32, color: Rgba) {
+        let clip = |value: f32, max: usize| ((value - 0.5).ceil().max(0.) as usize).min(max);
+        let (x0, x1) = (clip(left, self.width), clip(right, self.width));
+        let (y0, y1) = (clip(top, self.height), clip(bottom, self.height));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.blend_pixel(x, y, color);
+            }
+        }
+    }
+
+    // blends every pixel around the bounds whose center passes the test
+    fn blend_where<F: Fn(Vector2<f32>) -> bool>(
+        &mut self,
+        min: Vector2<f32>,
+        max: Vector2<f32>,
+        color: Rgba,
+        covers: F,
+    ) {
+        let clip = |value: f32, max: usize| (value.max(0.) as usize).min(max);
+        for y in clip(min.y.floor(), self.height)..clip(max.y.ceil(), self.height) {
+            for x in clip(min.x.floor(), self.width)..clip(max.x.ceil(), self.width) {
+                if covers(Vector2::new(x as f32 + 0.5, y as f32 + 0.5)) {
+                    self.blend_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn blend_pixel(&mut self, x: usize, y: usize, color: Rgba) {
+        let alpha = color.a.clamp(0., 1.);
+        let source = [color.r, color.g, color.b];
+        let index = (y * self.width + x) * 4;
+        for (channel, source) in self.pixels[index..index + 3].iter_mut().zip(source.iter()) {
+            let blended = source * alpha * 255. + *channel as f32 * (1. - alpha);
+            *channel = blended.round().clamp(0., 255.) as u8;
+        }
+    }
+}
+
+fn to_bytes(color: Rgba) -> [u8; 4] {
+    [
+        (color.r.clamp(0., 1.) * 255.).round() as u8,
+        (color.g.clamp(0., 1.) * 255.).round() as u8,
+        (color.b.clamp(0., 1.) * 255.).round() as u8,
+        (color.a.clamp(0., 1.) * 255.).round() as u8,
+    ]
+}
+
+impl Painter for Canvas {
+    fn fill_rect(&mut self, bottom_left: &Vector2<f32>, top_right: &Vector2<f32>, color: Rgba) {
+        self.blend_rect(
+            bottom_left.x.min(top_right.x),
+            bottom_left.y.min(top_right.y),
+            bottom_left.x.max(top_right.x),
+            bottom_left.y.max(top_right.y),
+            color,
+        );
+    }
+
+    fn stroke_rect(
+        &mut self,
+        bottom_left: &Vector2<f32>,
+        top_right: &Vector2<f32>,
+        width: f32,
+        color: Rgba,
+    ) {
+        let half = width * 0.5;
+        let left = bottom_left.x.min(top_right.x);
+        let right = bottom_left.x.max(top_right.x);
+        let top = bottom_left.y.min(top_right.y);
+        let bottom = bottom_left.y.max(top_right.y);
+        // the horizontal edges cover the corners, the vertical ones fit in between
+        self.blend_rect(left - half, top - half, right + half, top + half, color);
+        self.blend_rect(
+            left - half,
+            bottom - half,
+            right + half,
+            bottom + half,
+            color,
+        );
+        self.blend_rect(left - half, top + half, left + half, bottom - half, color);
+        self.blend_rect(right - half, top + half, right + half, bottom - half, color);
+    }
+
+    // a pixel is covered when its center is within half the width of the segment
+    fn line(&mut self, start: &Vector2<f32>, end: &Vector2<f32>, width: f32, color: Rgba) {
+        let half = width * 0.5;
+        let (start, end) = (*start, *end);
+        let along = end - start;
+        let length_squared = Vector2::dot(along, along);
+        self.blend_where(
+            Vector2::new(start.x.min(end.x) - half, start.y.min(end.y) - half),
+            Vector2::new(start.x.max(end.x) + half, start.y.max(end.y) + half),
+            color,
+            |point| {
+                let t = if length_squared > 0. {
+                    (Vector2::dot(point - start, along) / length_squared).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                point.distance(start + along * t) <= half
+            },
+        );
+    }
+
+    fn fill_circle(&mut self, center: &Vector2<f32>, radius: f32, color: Rgba) {
+        let center = *center;
+        let reach = Vector2::new(radius, radius);
+        self.blend_where(center - reach, center + reach, color, |point| {
+            point.distance(center) <= radius
+        });
+    }
+
+    fn stroke_circle(&mut self, center: &Vector2<f32>, radius: f32, width: f32, color: Rgba) {
+        let half = width * 0.5;
+        let center = *center;
+        let reach = Vector2::new(radius + half, radius + half);
+        self.blend_where(center - reach, center + reach, color, |point| {
+            (point.distance(center) - radius).abs() <= half
+        });
+    }
+}
@@ -0,0 +1,316 @@
+use crate::heatmap::HeatmapMode;
+use crate::raster::Canvas;
+use crate::simulation_app::SimulationApp;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+// records the arena into an animated GIF or APNG, one frame per interval of simulated time
+
+// simulated milliseconds between frames, also how long each frame is shown
+const FRAME_INTERVAL_MS: i64 = 100;
+// NeuQuant sampling factor of the GIF quantizer, from 1(best) to 30(fastest)
+const GIF_SPEED: i32 = 10;
+
+enum Format {
+    Gif(gif::Encoder<BufWriter<File>>),
+    // APNG needs the number of frames up front, so the compressed frames are kept until finish
+    Apng { path: String, frames: Vec<Vec<u8>> },
+}
+
+pub struct Recorder {
+    format: Format,
+    width: usize,
+    height: usize,
+    next_frame_ms: i64,
+    last_time_ms: i64,
+}
+
+impl Recorder {
+    // the format is picked by the extension, .gif for GIF and .png or .apng for APNG
+    pub fn create(path: &str, width: usize, height: usize) -> Result<Recorder, io::Error> {
+        let lowercase = path.to_lowercase();
+        let format = if lowercase.ends_with(".gif") {
+            let mut encoder = gif::Encoder::new(
+                BufWriter::new(File::create(path)?),
+                width as u16,
+                height as u16,
+                &[],
+            )
+            .map_err(gif_error)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(gif_error)?;
+            Format::Gif(encoder)
+        } else if lowercase.ends_with(".png") || lowercase.ends_with(".apng") {
+            // fail early rather than after the whole recording
+            File::create(path)?;
+            Format::Apng {
+                path: path.to_string(),
+                frames: vec![],
+            }
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown recording format {}, use .gif or .png", path),
+            ));
+        };
+        Ok(Recorder {
+            format,
+            width,
+            height,
+            next_frame_ms: 0,
+            last_time_ms: 0,
+        })
+    }
+
+    // adds a frame when a frame interval of simulated time has passed since the last one
+    pub fn capture(
+        &mut self,
+        simulation: &SimulationApp,
+        heatmap_mode: HeatmapMode,
+    ) -> Result<(), io::Error> {
+        let time_ms = simulation.latest_counts().time_ms;
+        // the simulation was restarted
+        if time_ms < self.last_time_ms {
+            self.next_frame_ms = time_ms;
+        }
+        self.last_time_ms = time_ms;
+        if time_ms < self.next_frame_ms {
+            return Ok(());
+        }
+        self.next_frame_ms = time_ms - time_ms % FRAME_INTERVAL_MS + FRAME_INTERVAL_MS;
+        self.add_frame(&simulation.rasterize(heatmap_mode))
+    }
+
+    pub fn add_frame(&mut self, canvas: &Canvas) -> Result<(), io::Error> {
+        if canvas.width() != self.width || canvas.height() != self.height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the arena changed size during the recording",
+            ));
+        }
+        match &mut self.format {
+            Format::Gif(encoder) => {
+                let mut pixels = canvas.pixels().to_vec();
+                let mut frame = gif::Frame::from_rgba_speed(
+                    self.width as u16,
+                    self.height as u16,
+                    &mut pixels,
+                    GIF_SPEED,
+                );
+                frame.delay = (FRAME_INTERVAL_MS / 10) as u16; // in hundredths of a second
+                encoder.write_frame(&frame).map_err(gif_error)
+            }
+            Format::Apng { frames, .. } => {
+                let mut png = vec![];
+                canvas.encode_png(&mut png)?;
+                frames.push(image_data(&png));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<(), io::Error> {
+        match self.format {
+            Format::Gif(encoder) => encoder.into_inner()?.flush(),
+            Format::Apng { path, frames } => write_apng(&path, self.width, self.height, &frames),
+        }
+    }
+}
+
+// io::Error::other needs Rust 1.74
+#[allow(clippy::io_other_error)]
+fn gif_error(error: gif::EncodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error)
+}
+
+// the concatenated IDAT chunks of an encoded PNG, which is the compressed image
+fn image_data(png: &[u8]) -> Vec<u8> {
+    let mut data = vec![];
+    let mut offset = 8; // skips the signature
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes([
+            png[offset],
+            png[offset + 1],
+            png[offset + 2],
+            png[offset + 3],
+        ]) as usize;
+        let chunk_type = &png[offset + 4..offset + 8];
+        let start = offset + 8;
+        if chunk_type == b"IDAT" {
+            data.extend_from_slice(&png[start..start + length]);
+        }
+        offset = start + length + 4; // skips the CRC
+    }
+    data
+}
+
+fn write_apng(
+    path: &str,
+    width: usize,
+    height: usize,
+    frames: &[Vec<u8>],
+) -> Result<(), io::Error> {
+    if frames.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "an APNG needs at least one frame",
+        ));
+    }
+    // unbuffered, as the writer can only write its final chunk when dropped
+    encode_apng(File::create(path)?, width, height, frames)
+}
+
+// every frame covers the whole image and replaces the previous one
+fn encode_apng<W: Write>(
+    output: W,
+    width: usize,
+    height: usize,
+    frames: &[Vec<u8>],
+) -> Result<(), io::Error> {
+    let mut encoder = png::Encoder::new(output, width as u32, height as u32);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+
+    let mut animation_control = vec![];
+    animation_control.extend_from_slice(&(frames.len() as u32).to_be_bytes());
+    animation_control.extend_from_slice(&0u32.to_be_bytes()); // loops forever
+    writer.write_chunk(*b"acTL", &animation_control)?;
+
+    let mut sequence: u32 = 0;
+    for (index, frame) in frames.iter().enumerate() {
+        let mut frame_control = vec![];
+        frame_control.extend_from_slice(&sequence.to_be_bytes());
+        frame_control.extend_from_slice(&(width as u32).to_be_bytes());
+        frame_control.extend_from_slice(&(height as u32).to_be_bytes());
+        frame_control.extend_from_slice(&0u32.to_be_bytes()); // x offset
+        frame_control.extend_from_slice(&0u32.to_be_bytes()); // y offset
+        frame_control.extend_from_slice(&(FRAME_INTERVAL_MS as u16).to_be_bytes());
+        frame_control.extend_from_slice(&1000u16.to_be_bytes()); // delay is in milliseconds
+        frame_control.push(0); // dispose: none
+        frame_control.push(0); // blend: source
+        writer.write_chunk(*b"fcTL", &frame_control)?;
+        sequence += 1;
+
+        // the first frame doubles as the still image shown by viewers without APNG support
+        if index == 0 {
+            writer.write_chunk(*b"IDAT", frame)?;
+        } else {
+            let mut frame_data = Vec::with_capacity(frame.len() + 4);
+            frame_data.extend_from_slice(&sequence.to_be_bytes());
+            frame_data.extend_from_slice(frame);
+            writer.write_chunk(*b"fdAT", &frame_data)?;
+            sequence += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rgx::core::Rgba;
+
+    struct Chunk {
+        chunk_type: [u8; 4],
+        data: Vec<u8>,
+        crc: u32,
+    }
+
+    fn be_u32(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    // the CRC-32 PNG chunks end with, computed bit by bit
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = 0xffff_ffffu32;
+        for byte in bytes.iter() {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    fn chunks(png: &[u8]) -> Vec<Chunk> {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = vec![];
+        let mut offset = 8;
+        while offset < png.len() {
+            let length = be_u32(&png[offset..]) as usize;
+            let start = offset + 8;
+            let mut chunk_type = [0; 4];
+            chunk_type.copy_from_slice(&png[offset + 4..start]);
+            chunks.push(Chunk {
+                chunk_type,
+                data: png[start..start + length].to_vec(),
+                crc: be_u32(&png[start + length..]),
+            });
+            offset = start + length + 4;
+        }
+        chunks
+    }
+
+    fn frame(color: Rgba) -> Vec<u8> {
+        let mut png = vec![];
+        Canvas::new(3, 2, color).encode_png(&mut png).unwrap();
+        image_data(&png)
+    }
+
+    #[test]
+    fn apng_chunks_are_numbered_and_checksummed() {
+        let frames = vec![
+            frame(Rgba::new(1., 0., 0., 1.)),
+            frame(Rgba::new(0., 0., 1., 1.)),
+        ];
+        let mut apng = vec![];
+        encode_apng(&mut apng, 3, 2, &frames).unwrap();
+        let chunks = chunks(&apng);
+
+        for chunk in chunks.iter() {
+            let mut checked = chunk.chunk_type.to_vec();
+            checked.extend_from_slice(&chunk.data);
+            assert_eq!(chunk.crc, crc32(&checked));
+        }
+        let types: Vec<&[u8]> = chunks.iter().map(|chunk| &chunk.chunk_type[..]).collect();
+        assert_eq!(
+            types,
+            vec![
+                &b"IHDR"[..],
+                b"acTL",
+                b"fcTL",
+                b"IDAT",
+                b"fcTL",
+                b"fdAT",
+                b"IEND"
+            ]
+        );
+
+        // two frames, looping forever
+        assert_eq!(be_u32(&chunks[1].data), 2);
+        assert_eq!(be_u32(&chunks[1].data[4..]), 0);
+        // fcTL, fcTL and fdAT share one sequence
+        assert_eq!(be_u32(&chunks[2].data), 0);
+        assert_eq!(be_u32(&chunks[4].data), 1);
+        assert_eq!(be_u32(&chunks[5].data), 2);
+        // the frames are whole images of 3 by 2
+        assert_eq!(be_u32(&chunks[4].data[4..]), 3);
+        assert_eq!(be_u32(&chunks[4].data[8..]), 2);
+        assert_eq!(chunks[3].data, frames[0]);
+        assert_eq!(&chunks[5].data[4..], &frames[1][..]);
+    }
+
+    #[test]
+    fn an_apng_needs_a_frame() {
+        let path = std::env::temp_dir().join(format!("recording_{}.png", std::process::id()));
+        let error = write_apng(path.to_str().unwrap(), 3, 2, &[]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::area::OrganismId;
+use crate::genealogy::InfectionEvent;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+// reproduction numbers estimated from the infection genealogy
+
+// how often the rolling window is evaluated, in fractions of its length
+const RT_SAMPLES_PER_WINDOW: i64 = 4;
+
+pub struct RtEstimate {
+    pub time_ms: i64,       // end of the rolling window
+    pub rt: f32,            // mean secondary cases of the infections in the window
+    pub cohort_size: usize, // resolved infections in the window
+}
+
+pub struct ReproductionNumbers {
+    pub r0: Option<f32>, // mean secondary cases of the initially infected, leaving out manual ones
+    pub rt: Vec<RtEstimate>,
+    pub secondary_cases: Vec<usize>, // number of resolved infections causing index secondary cases
+}
+
+impl ReproductionNumbers {
+    // only infections that are resolved(recovered or dead) count, as the others may still infect.
+    // Rt is the mean over the infections of the last window_ms, evaluated every quarter window
+    pub fn estimate<F>(events: &[InfectionEvent], is_resolved: F, window_ms: i64) -> Self
+    where
+        F: Fn(OrganismId) -> bool,
+    {
+        let mut secondary: HashMap<OrganismId, usize> = HashMap::new();
+        for event in events.iter() {
+            if let Some(infector) = event.infector {
+                *secondary.entry(infector).or_insert(0) += 1;
+            }
+        }
+        let cases_of = |organism_id| *secondary.get(&organism_id).unwrap_or(&0);
+
+        // in time order, as events are recorded as they happen
+        let resolved: Vec<(i64, usize)> = events
+            .iter()
+            .filter(|event| is_resolved(event.infectee))
+            .map(|event| (event.time_ms, cases_of(event.infectee)))
+            .collect();
+
+        let seeds: Vec<usize> = events
+            .iter()
+            .filter(|event| {
+                event.infector.is_none() && !event.manual && is_resolved(event.infectee)
+            })
+            .map(|event| cases_of(event.infectee))
+            .collect();
+        let r0 = if seeds.is_empty() {
+            None
+        } else {
+            Some(seeds.iter().sum::<usize>() as f32 / seeds.len() as f32)
+        };
+
+        let mut secondary_cases = vec![];
+        for (_, cases) in resolved.iter() {
+            if secondary_cases.len() <= *cases {
+                secondary_cases.resize(cases + 1, 0);
+            }
+            secondary_cases[*cases] += 1;
+        }
+
+        ReproductionNumbers {
+            r0,
+            rt: Self::rolling_rt(&resolved, window_ms),
+            secondary_cases,
+        }
+    }
+
+    // windows without resolved infections are left out
+    fn rolling_rt(resolved: &[(i64, usize)], window_ms: i64) -> Vec<RtEstimate> {
+        let mut rt = vec![];
+        let (first_ms, last_ms) = match (resolved.first(), resolved.last()) {
+            (Some(first), Some(last)) if window_ms > 0 => (first.0, last.0),
+            _ => return rt,
+        };
+        let step_ms = (window_ms / RT_SAMPLES_PER_WINDOW).max(1);
+        // the infections from start up to end are inside the window [end_ms - window_ms, end_ms)
+        let (mut start, mut end) = (0, 0);
+        let mut cases = 0;
+        let mut end_ms = (first_ms / step_ms + 1) * step_ms;
+        while end_ms <= (last_ms / step_ms + 1) * step_ms {
+            while end < resolved.len() && resolved[end].0 < end_ms {
+                cases += resolved[end].1;
+                end += 1;
+            }
+            while start < end && resolved[start].0 < end_ms - window_ms {
+                cases -= resolved[start].1;
+                start += 1;
+            }
+            if end > start {
+                rt.push(RtEstimate {
+                    time_ms: end_ms,
+                    rt: cases as f32 / (end - start) as f32,
+                    cohort_size: end - start,
+                });
+            }
+            end_ms += step_ms;
+        }
+        rt
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let r0 = match self.r0 {
+            Some(r0) => r0.to_string(),
+            None => String::from("null"),
+        };
+        writeln!(file, "{{")?;
+        writeln!(file, "  \"r0\": {},", r0)?;
+        writeln!(file, "  \"rt\": [")?;
+        for (index, estimate) in self.rt.iter().enumerate() {
+            let separator = if index + 1 < self.rt.len() { "," } else { "" };
+            writeln!(
+                file,
+                "    {{\"time_ms\": {}, \"rt\": {}, \"cohort_size\": {}}}{}",
+                estimate.time_ms, estimate.rt, estimate.cohort_size, separator
+            )?;
+        }
+        writeln!(file, "  ],")?;
+        let secondary_cases: Vec<String> = self
+            .secondary_cases
+            .iter()
+            .map(|count| count.to_string())
+            .collect();
+        writeln!(
+            file,
+            "  \"secondary_cases\": [{}]",
+            secondary_cases.join(", ")
+        )?;
+        writeln!(file, "}}")?;
+        file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genealogy::event;
+
+    // seeds 0 and 1, where 0 infects 2 and 3 and 2 infects 4
+    fn events() -> Vec<InfectionEvent> {
+        vec![
+            event(None, 0, 0),
+            event(None, 1, 0),
+            event(Some(0), 2, 100),
+            event(Some(0), 3, 150),
+            event(Some(2), 4, 300),
+        ]
+    }
+
+    #[test]
+    fn r0_only_counts_resolved_seeds() {
+        let numbers = ReproductionNumbers::estimate(&events(), |organism_id| organism_id != 1, 100);
+        assert_eq!(numbers.r0, Some(2.));
+
+        let numbers = ReproductionNumbers::estimate(&events(), |_| true, 100);
+        assert_eq!(numbers.r0, Some(1.));
+
+        let numbers = ReproductionNumbers::estimate(&events(), |_| false, 100);
+        assert_eq!(numbers.r0, None);
+    }
+
+    #[test]
+    fn r0_leaves_out_manual_infections() {
+        let mut events = events();
+        events.push(InfectionEvent {
+            manual: true,
+            ..event(None, 5, 200)
+        });
+        let numbers = ReproductionNumbers::estimate(&events, |_| true, 100);
+        assert_eq!(numbers.r0, Some(1.));
+    }
+
+    #[test]
+    fn secondary_case_distribution() {
+        let numbers = ReproductionNumbers::estimate(&events(), |_| true, 100);
+        // 1, 3 and 4 infected nobody, 2 infected one and 0 infected two
+        assert_eq!(numbers.secondary_cases, vec![3, 1, 1]);
+    }
+
+    #[test]
+    fn rt_rolls_over_the_window() {
+        let numbers = ReproductionNumbers::estimate(&events(), |_| true, 200);
+        let rt: Vec<(i64, f32, usize)> = numbers
+            .rt
+            .iter()
+            .map(|estimate| (estimate.time_ms, estimate.rt, estimate.cohort_size))
+            .collect();
+        assert_eq!(
+            rt,
+            vec![
+                (50, 1., 2),    // the seeds
+                (100, 1., 2),   // still the seeds
+                (150, 1., 3),   // and 2
+                (200, 0.75, 4), // and 3
+                (250, 0.5, 2),  // the seeds left the window
+                (300, 0.5, 2),  // 2 and 3
+                (350, 0.0, 2),  // 3 and 4
+            ]
+        );
+    }
+
+    #[test]
+    fn a_zero_window_has_no_rt() {
+        let numbers = ReproductionNumbers::estimate(&events(), |_| true, 0);
+        assert!(numbers.rt.is_empty());
+        assert_eq!(numbers.r0, Some(1.));
+    }
+
+    #[test]
+    fn no_events_estimate_nothing() {
+        let numbers = ReproductionNumbers::estimate(&[], |_| true, 100);
+        assert_eq!(numbers.r0, None);
+        assert!(numbers.rt.is_empty());
+        assert!(numbers.secondary_cases.is_empty());
+    }
+}
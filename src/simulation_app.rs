@@ -1,7 +1,31 @@
+use crate::area::OrganismId;
+use crate::config::SimulationConfig;
+use crate::contact_network::ContactNetwork;
+use crate::curve_export::CurveWriter;
+use crate::density_map::DensityMap;
+use crate::effects::{render_infection_rings, Trails};
+use crate::genealogy::{Genealogy, InfectionEvent};
 use crate::grid_system::GridSystem;
-use crate::organism::OrganismState;
-use crate::window_box::WindowBox;
-use rgx::kit::shape2d::Batch;
+use crate::heatmap::{render_grid_lines, render_heatmap, HeatmapMode};
+use crate::organism::{InfectionState, OrganismState};
+use crate::painter::Painter;
+use crate::raster::Canvas;
+use crate::reproduction::ReproductionNumbers;
+use crate::statistics::CompartmentCounts;
+use crate::svg::SvgDocument;
+use crate::window_box::{Wall, WindowBox};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rgx::core::*;
+use rgx::math::*;
+use std::io;
+
+// simulated milliseconds of every tick, fixed so a seed replays the same run at any frame rate
+pub const STEP_MS: i64 = 16;
+// how far from a click an organism can be picked, in pixels
+const PICK_RADIUS: f32 = 10.;
+const WALL_WIDTH: f32 = 2.;
+const WALL_COLOR: Rgba = Rgba::new(0.8, 0.8, 0.8, 1.0);
 
 pub struct WindowAttributes {
     pub width: i32,
@@ -10,72 +34,537 @@ pub struct WindowAttributes {
 // Contagious, Symptomatic, Recovered, Dead
 
 pub struct SimulationApp {
+    arena_width: i32,
+    arena_height: i32,
+    config: SimulationConfig,
     window_box: WindowBox,
     organisms: Vec<OrganismState>,
     grid_system: GridSystem,
+    time_ms: i64, // simulated time
+    counts: Vec<CompartmentCounts>,
+    curve_writer: Option<CurveWriter>,
+    genealogy: Genealogy,
+    cell_infections: Vec<usize>, // cumulative infections per grid cell
+    population_grid: GridSystem, // every organism, dead ones included, rebuilt each tick
+    manual_infections: usize,    // infected by clicking since the last tick, counted with the next
+    contact_network: Option<ContactNetwork>,
+    trails: Option<Trails>,
+    seed: u64,
+    rng: StdRng, // every random draw of the run comes from here so a seed replays it
     frame: u32,
 }
 
 impl SimulationApp {
-    pub fn new(window: WindowAttributes) -> SimulationApp {
-        let num_organisms = 6000;
-        let num_initially_infected = 200;
-        let percent_in_place = 90.;
-        let organism_size = 3.0;
-        let infection_lifetime_ms = 1000;
-        let fatality_rate = 2.0;
-        let grid_pixel_size = 25;
-        let max_velocity = 100.;
-
-        let window_box = WindowBox::new(window.width, window.height);
+    pub fn new(config: SimulationConfig, seed: u64) -> Result<SimulationApp, io::Error> {
+        let mut simulation = SimulationApp::populate(config, seed)?;
+        simulation.create_curve_writer()?;
+        Ok(simulation)
+    }
+
+    // everything but the output files, which are only created once a run is about to start
+    fn populate(config: SimulationConfig, seed: u64) -> Result<SimulationApp, io::Error> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let arena_width = config.arena_width;
+        let arena_height = config.arena_height;
+        let window_box = WindowBox::new(arena_width, arena_height, config.boundary_mode);
         let mut organisms = Vec::new();
-        let mut grid_system = GridSystem::new(window.width, window.height, grid_pixel_size);
+        let mut grid_system = GridSystem::new(
+            arena_width,
+            arena_height,
+            config.grid_pixel_size,
+            config.boundary_mode,
+        );
+        let population_grid = GridSystem::new(
+            arena_width,
+            arena_height,
+            config.grid_pixel_size,
+            config.boundary_mode,
+        );
+        let contact_network =
+            if config.contact_graphml_path.is_some() || config.contact_dot_path.is_some() {
+                Some(ContactNetwork::default())
+            } else {
+                None
+            };
         let mut num_infected = 0;
+        let mut genealogy = Genealogy::default();
+        let mut cell_infections = vec![0; grid_system.num_cells()];
+        let density_map = match config.density_map_path.as_ref() {
+            Some(path) => Some(DensityMap::load(path)?),
+            None => None,
+        };
 
-        for _i in 1..num_organisms {
+        for _i in 0..config.num_organisms {
             let mut organism = OrganismState::random(
-                window.width as f32,
-                window.height as f32,
-                organism_size,
-                max_velocity,
-                percent_in_place,
-                infection_lifetime_ms,
-                fatality_rate,
+                &config,
+                density_map.as_ref(),
+                organisms.len(),
                 &grid_system,
+                &mut rng,
             );
-            if num_infected < num_initially_infected && organism.velocity > 0. {
+            if num_infected < config.num_initially_infected && organism.velocity > 0. {
                 organism.set_infected(&mut grid_system);
+                if let Some(count) =
+                    cell_infections.get_mut(grid_system.get_grid_index(&organism.position))
+                {
+                    *count += 1;
+                }
+                genealogy.record(InfectionEvent {
+                    infector: None,
+                    infectee: organism.organism_id(),
+                    time_ms: 0,
+                    position: organism.position,
+                    manual: false,
+                });
             }
 
             organisms.push(organism);
             num_infected = num_infected + 1;
         }
 
-        SimulationApp {
+        let counts = vec![CompartmentCounts::count(0, &organisms, 0, 0, None)];
+        let trails = if config.trail_length_ms > 0 {
+            let mut trails = Trails::new(config.trail_length_ms, organisms.len());
+            trails.record(0, &organisms);
+            Some(trails)
+        } else {
+            None
+        };
+        let mut simulation = SimulationApp {
+            arena_width,
+            arena_height,
+            config,
             window_box,
             organisms,
             grid_system,
+            time_ms: 0,
+            counts,
+            curve_writer: None,
+            genealogy,
+            cell_infections,
+            population_grid,
+            manual_infections: 0,
+            contact_network,
+            trails,
+            seed,
+            rng,
             frame: 0,
+        };
+        simulation.rebuild_population_grid();
+        Ok(simulation)
+    }
+
+    fn create_curve_writer(&mut self) -> Result<(), io::Error> {
+        if self.config.curve_csv_path.is_none() && self.config.curve_json_path.is_none() {
+            return Ok(());
         }
+        let mut curve_writer = CurveWriter::create(
+            self.config.curve_csv_path.as_deref(),
+            self.config.curve_json_path.as_deref(),
+        )?;
+        curve_writer.write(self.latest_counts())?;
+        self.curve_writer = Some(curve_writer);
+        Ok(())
+    }
+
+    // finishes the current run and starts over with config, keeping the walls. When the new run
+    // can't be set up the current one goes on. The curve files are created only after the
+    // current run closed its own, which may have the same paths, so failing to create them
+    // leaves the new run without a curve
+    pub fn reset(&mut self, config: SimulationConfig, seed: u64) -> Result<(), io::Error> {
+        let mut simulation = SimulationApp::populate(config, seed)?;
+        for wall in self.window_box.walls() {
+            simulation.window_box.add_wall(*wall);
+        }
+        self.finish();
+        *self = simulation;
+        self.create_curve_writer()
     }
 
     pub fn update(&mut self, delta_time: i64) {
+        let prevalence = self.latest_counts().prevalence();
+
         // update all positions
         for organism in self.organisms.iter_mut() {
-            organism.update(delta_time, &self.window_box);
+            organism.update(
+                delta_time,
+                &self.window_box,
+                &self.grid_system,
+                self.config.fear_response.as_ref(),
+                prevalence,
+                &mut self.rng,
+            );
         }
-        // positions updated, now check for intersections
+        // positions updated, now check for contacts and intersections
+        self.rebuild_population_grid();
+        if self.contact_network.is_some() {
+            self.record_contacts(delta_time);
+        }
+        self.time_ms += delta_time;
+        if let Some(trails) = self.trails.as_mut() {
+            trails.record(self.time_ms, &self.organisms);
+        }
+        let mut new_infections = self.manual_infections;
+        self.manual_infections = 0;
+        let mut new_deaths = 0;
         for organism in self.organisms.iter_mut() {
-            organism.check_infected(delta_time, &mut self.grid_system);
+            let was_dead = organism.infection_state() == InfectionState::Dead;
+            if let Some(infector) =
+                organism.check_infected(delta_time, &mut self.grid_system, &mut self.rng)
+            {
+                new_infections += 1;
+                if let Some(count) = self
+                    .cell_infections
+                    .get_mut(self.grid_system.get_grid_index(&organism.position))
+                {
+                    *count += 1;
+                }
+                self.genealogy.record(InfectionEvent {
+                    infector: Some(infector),
+                    infectee: organism.organism_id(),
+                    time_ms: self.time_ms,
+                    position: organism.position,
+                    manual: false,
+                });
+            }
+            if !was_dead && organism.infection_state() == InfectionState::Dead {
+                new_deaths += 1;
+            }
         }
         self.frame = self.frame + 1;
+
+        let counts = CompartmentCounts::count(
+            self.time_ms,
+            &self.organisms,
+            new_infections,
+            new_deaths,
+            self.counts.last(),
+        );
+        self.counts.push(counts);
+
+        if let Some(curve_writer) = self.curve_writer.as_mut() {
+            if let Err(error) = curve_writer.write(&counts) {
+                eprintln!("failed to write epidemic curve: {}", error);
+                self.curve_writer = None;
+            }
+        }
+    }
+
+    // flushes everything written incrementally, call once before exiting
+    pub fn finish(&mut self) {
+        if let Some(curve_writer) = self.curve_writer.as_mut() {
+            if let Err(error) = curve_writer.finish() {
+                eprintln!("failed to write epidemic curve: {}", error);
+            }
+        }
+        self.curve_writer = None;
+
+        if let Some(path) = self.config.genealogy_edge_list_path.as_ref() {
+            if let Err(error) = self.genealogy.write_edge_list(path) {
+                eprintln!("failed to write infection edge list: {}", error);
+            }
+        }
+        if let Some(path) = self.config.genealogy_newick_path.as_ref() {
+            if let Err(error) = self.genealogy.write_newick(path) {
+                eprintln!("failed to write infection tree: {}", error);
+            }
+        }
+        if let Some(path) = self.config.reproduction_json_path.as_ref() {
+            if let Err(error) = self.reproduction_numbers().write_json(path) {
+                eprintln!("failed to write reproduction numbers: {}", error);
+            }
+        }
+        if let Some(contact_network) = self.contact_network.as_ref() {
+            if let Some(path) = self.config.contact_graphml_path.as_ref() {
+                if let Err(error) = contact_network.write_graphml(path, self.organisms.len()) {
+                    eprintln!("failed to write contact network: {}", error);
+                }
+            }
+            if let Some(path) = self.config.contact_dot_path.as_ref() {
+                if let Err(error) = contact_network.write_dot(path, self.organisms.len()) {
+                    eprintln!("failed to write contact network: {}", error);
+                }
+            }
+        }
+    }
+
+    fn rebuild_population_grid(&mut self) {
+        self.population_grid.clear();
+        for organism in self.organisms.iter() {
+            organism.add_to_grid(&mut self.population_grid);
+        }
+    }
+
+    fn record_contacts(&mut self, delta_time: i64) {
+        let organisms = &self.organisms;
+        if let Some(contact_network) = self.contact_network.as_mut() {
+            contact_network.next_tick();
+            for organism in organisms.iter() {
+                if organism.infection_state() == InfectionState::Dead {
+                    continue;
+                }
+                let organism_id = organism.organism_id();
+                self.population_grid.for_each_area_in_radius(
+                    &organism.position,
+                    self.config.contact_radius,
+                    |area, _offset| {
+                        // each pair is found from both sides, record it once. The dead make
+                        // no contacts
+                        if area.organism_id > organism_id
+                            && organisms[area.organism_id].infection_state() != InfectionState::Dead
+                        {
+                            contact_network.record(organism_id, area.organism_id, delta_time);
+                        }
+                    },
+                );
+            }
+        }
+    }
+
+    // the organism closest to position, dead ones included, if any is within reach
+    pub fn pick_organism(&self, position: &Vector2<f32>) -> Option<OrganismId> {
+        let mut nearest = None;
+        let mut nearest_distance = PICK_RADIUS;
+        self.population_grid
+            .for_each_area_in_radius(position, PICK_RADIUS, |area, offset| {
+                let distance = offset.magnitude();
+                if distance <= nearest_distance {
+                    nearest = Some(area.organism_id);
+                    nearest_distance = distance;
+                }
+            });
+        nearest
+    }
+
+    // seeds an infection by hand, a root of the genealogy that R0 leaves out
+    pub fn infect_organism(&mut self, organism_id: OrganismId) {
+        if let Some(organism) = self.organisms.get_mut(organism_id) {
+            if organism.infection_state() != InfectionState::Uninfected {
+                return;
+            }
+            organism.set_infected(&mut self.grid_system);
+            if let Some(count) = self
+                .cell_infections
+                .get_mut(self.grid_system.get_grid_index(&organism.position))
+            {
+                *count += 1;
+            }
+            self.genealogy.record(InfectionEvent {
+                infector: None,
+                infectee: organism_id,
+                time_ms: self.time_ms,
+                position: organism.position,
+                manual: true,
+            });
+            self.manual_infections += 1;
+        }
+    }
+
+    pub fn immunize_organism(&mut self, organism_id: OrganismId) {
+        if let Some(organism) = self.organisms.get_mut(organism_id) {
+            organism.immunize();
+        }
+    }
+
+    pub fn add_wall(&mut self, wall: Wall) {
+        self.window_box.add_wall(wall);
+    }
+
+    pub fn erase_walls(&mut self, start: &Vector2<f32>, end: &Vector2<f32>) {
+        self.window_box.erase_walls(start, end);
+    }
+
+    pub fn organism(&self, organism_id: OrganismId) -> Option<&OrganismState> {
+        self.organisms.get(organism_id)
+    }
+
+    pub fn genealogy(&self) -> &Genealogy {
+        &self.genealogy
+    }
+
+    pub fn arena_size(&self) -> Vector2<f32> {
+        Vector2::new(self.arena_width as f32, self.arena_height as f32)
+    }
+
+    // one entry per tick, starting with the initial state at time 0
+    pub fn counts(&self) -> &[CompartmentCounts] {
+        &self.counts
     }
 
-    pub fn render(&self) -> Batch {
-        let mut batch = Batch::new();
+    pub fn reproduction_numbers(&self) -> ReproductionNumbers {
+        ReproductionNumbers::estimate(
+            self.genealogy.events(),
+            |organism_id| !self.organisms[organism_id].is_contagious(),
+            self.config.rt_window_ms,
+        )
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn latest_counts(&self) -> &CompartmentCounts {
+        self.counts.last().unwrap()
+    }
+
+    pub fn render<P: Painter>(&self, painter: &mut P, heatmap_mode: HeatmapMode) {
+        // drawn first so it stays under the organisms
+        match heatmap_mode {
+            HeatmapMode::Off => {}
+            HeatmapMode::Infected => {
+                let infected: Vec<usize> = (0..self.grid_system.num_cells())
+                    .map(|grid_id| self.grid_system.num_areas_in_grid(grid_id))
+                    .collect();
+                render_heatmap(painter, &self.grid_system, &infected);
+            }
+            HeatmapMode::CumulativeInfections => {
+                render_heatmap(painter, &self.grid_system, &self.cell_infections);
+            }
+        }
+        if let Some(trails) = self.trails.as_ref() {
+            trails.render(
+                painter,
+                &self.organisms,
+                self.arena_size(),
+                &self.config.theme,
+            );
+        }
         for organism in self.organisms.iter() {
-            organism.render(&mut batch, self.frame);
+            organism.render(
+                painter,
+                &self.config.theme,
+                &self.config.organism_style,
+                self.config.max_velocity,
+                self.frame,
+            );
+        }
+        if self.config.infection_ring_ms > 0 {
+            render_infection_rings(
+                painter,
+                self.genealogy.events(),
+                self.time_ms,
+                self.config.infection_ring_ms,
+                self.config.organism_size,
+                &self.config.theme,
+            );
+        }
+        for wall in self.window_box.walls().iter() {
+            render_wall(painter, wall, WALL_COLOR);
+        }
+        // the arena's edges, visible once zoomed out
+        painter.stroke_rect(&Vector2::new(0., 0.), &self.arena_size(), 1.0, WALL_COLOR);
+    }
+
+    // draws the arena into memory at one pixel per arena pixel
+    pub fn rasterize(&self, heatmap_mode: HeatmapMode) -> Canvas {
+        let mut canvas = Canvas::new(
+            self.arena_width as usize,
+            self.arena_height as usize,
+            Rgba::new(0.0, 0.0, 0.0, 1.0),
+        );
+        self.render(&mut canvas, heatmap_mode);
+        canvas
+    }
+
+    // a vector snapshot of the arena, with the grid system's cells outlined underneath
+    pub fn export_svg(&self, heatmap_mode: HeatmapMode) -> SvgDocument {
+        let size = self.arena_size();
+        let mut document = SvgDocument::new(size.x, size.y, Rgba::new(0.0, 0.0, 0.0, 1.0));
+        render_grid_lines(&mut document, &self.grid_system, size);
+        self.render(&mut document, heatmap_mode);
+        document
+    }
+}
+
+pub fn render_wall<P: Painter>(painter: &mut P, wall: &Wall, color: Rgba) {
+    painter.line(&wall.start, &wall.end, WALL_WIDTH, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_organism_is_created() {
+        let config = SimulationConfig {
+            num_organisms: 300,
+            num_initially_infected: 10,
+            ..SimulationConfig::default()
+        };
+        let simulation = SimulationApp::new(config, 1).unwrap();
+        assert_eq!(simulation.organisms.len(), 300);
+        assert_eq!(simulation.latest_counts().population(), 300);
+    }
+
+    fn simulation(fatality_rate: f32) -> SimulationApp {
+        let config = SimulationConfig {
+            num_organisms: 300,
+            num_initially_infected: 100,
+            fatality_rate,
+            ..SimulationConfig::default()
+        };
+        SimulationApp::new(config, 1).unwrap()
+    }
+
+    // what the grid should find, looking at every organism
+    fn nearest(simulation: &SimulationApp, position: &Vector2<f32>) -> Option<OrganismId> {
+        simulation
+            .organisms
+            .iter()
+            .filter(|organism| organism.position.distance(*position) <= PICK_RADIUS)
+            .min_by(|first, second| {
+                let first = first.position.distance(*position);
+                let second = second.position.distance(*position);
+                first.partial_cmp(&second).unwrap()
+            })
+            .map(|organism| organism.organism_id())
+    }
+
+    #[test]
+    fn organisms_are_picked_across_cell_boundaries() {
+        let mut simulation = simulation(2.);
+        simulation.update(STEP_MS);
+        let grid_size = simulation.grid_system.grid_size();
+        // an organism a few pixels right of a cell's left edge, clicked left of that edge
+        let organism = simulation
+            .organisms
+            .iter()
+            .find(|organism| {
+                let inside = organism.position.x % grid_size;
+                organism.position.x > grid_size && inside > 1. && inside < 5.
+            })
+            .unwrap();
+        let edge = organism.position.x - organism.position.x % grid_size;
+        let click = Vector2::new(edge - 1., organism.position.y);
+        assert_ne!(
+            simulation.grid_system.get_grid_index(&click),
+            simulation.grid_system.get_grid_index(&organism.position)
+        );
+        assert!(nearest(&simulation, &click).is_some());
+        assert_eq!(
+            simulation.pick_organism(&click),
+            nearest(&simulation, &click)
+        );
+    }
+
+    #[test]
+    fn dead_organisms_can_be_picked() {
+        let mut simulation = simulation(100.);
+        while simulation.latest_counts().dead == 0 {
+            simulation.update(STEP_MS);
         }
-        batch
+        let dead = simulation
+            .organisms
+            .iter()
+            .find(|organism| organism.infection_state() == InfectionState::Dead)
+            .unwrap();
+        assert_eq!(
+            simulation.pick_organism(&dead.position),
+            Some(dead.organism_id())
+        );
     }
 }
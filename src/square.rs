@@ -34,6 +34,10 @@ impl Square {
         Square::new(self.center.clone(), self.size + self.size)
     }
 
+    pub fn translated(&self, offset: &Vector2<f32>) -> Square {
+        Square::new(self.center + *offset, self.size)
+    }
+
     pub fn intersects(&self, other: &Square) -> bool {
         let x_intersect = (other.bottom_left.x >= self.bottom_left.x
             && other.bottom_left.x <= self.top_right.x)
@@ -0,0 +1,122 @@
+use crate::organism::{InfectionState, OrganismState};
+// per tick compartment counts, the time series every analysis is built on
+
+#[derive(Clone, Copy, Default)]
+pub struct CompartmentCounts {
+    pub time_ms: i64, // simulated time
+    pub susceptible: usize,
+    pub infected: usize, // every contagious organism, symptomatic ones included
+    pub symptomatic: usize,
+    pub recovered: usize,
+    pub dead: usize,
+    pub new_infections: usize, // infections that happened during this tick
+    pub cumulative_infections: usize, // including the initially infected
+    pub cumulative_deaths: usize,
+}
+
+impl CompartmentCounts {
+    // previous is the last entry of the series, None for the initial state
+    pub fn count(
+        time_ms: i64,
+        organisms: &[OrganismState],
+        new_infections: usize,
+        new_deaths: usize,
+        previous: Option<&CompartmentCounts>,
+    ) -> Self {
+        let mut counts = CompartmentCounts {
+            time_ms,
+            new_infections,
+            ..Default::default()
+        };
+        for organism in organisms.iter() {
+            match organism.infection_state() {
+                InfectionState::Uninfected => counts.susceptible += 1,
+                InfectionState::Infected => counts.infected += 1,
+                InfectionState::Symptomatic => {
+                    counts.infected += 1;
+                    counts.symptomatic += 1;
+                }
+                InfectionState::Recovered => counts.recovered += 1,
+                InfectionState::Dead => counts.dead += 1,
+            }
+        }
+        counts.cumulative_infections = match previous {
+            Some(previous) => previous.cumulative_infections + new_infections,
+            None => counts.infected + counts.recovered + counts.dead,
+        };
+        counts.cumulative_deaths = match previous {
+            Some(previous) => previous.cumulative_deaths + new_deaths,
+            None => counts.dead,
+        };
+        counts
+    }
+
+    pub fn population(&self) -> usize {
+        self.susceptible + self.infected + self.recovered + self.dead
+    }
+
+    // share of the population that is currently contagious
+    pub fn prevalence(&self) -> f32 {
+        if self.population() == 0 {
+            return 0.;
+        }
+        self.infected as f32 / self.population() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SimulationConfig;
+    use crate::grid_system::GridSystem;
+    use crate::window_box::BoundaryMode;
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    // one organism in each state, dead by the end of its infection as fatality_rate is 100
+    fn population() -> Vec<OrganismState> {
+        let config = SimulationConfig {
+            fatality_rate: 100.,
+            ..SimulationConfig::default()
+        };
+        let mut grid_system = GridSystem::new(100, 100, 25, BoundaryMode::Reflect);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut organisms: Vec<OrganismState> = (0..5)
+            .map(|organism_id| {
+                OrganismState::random(&config, None, organism_id, &grid_system, &mut rng)
+            })
+            .collect();
+        for organism in organisms[1..4].iter_mut() {
+            organism.set_infected(&mut grid_system);
+        }
+        organisms[2].check_infected(config.symptom_onset_ms, &mut grid_system, &mut rng);
+        organisms[3].check_infected(config.infection_lifetime_ms, &mut grid_system, &mut rng);
+        organisms[4].immunize();
+        organisms
+    }
+
+    #[test]
+    fn organisms_are_counted_into_their_compartments() {
+        let counts = CompartmentCounts::count(0, &population(), 0, 0, None);
+        assert_eq!(counts.susceptible, 1);
+        assert_eq!(counts.infected, 2);
+        assert_eq!(counts.symptomatic, 1);
+        assert_eq!(counts.recovered, 1);
+        assert_eq!(counts.dead, 1);
+        assert_eq!(counts.population(), 5);
+        assert_eq!(counts.prevalence(), 0.4);
+        // the initial state counts everyone ever infected
+        assert_eq!(counts.cumulative_infections, 4);
+        assert_eq!(counts.cumulative_deaths, 1);
+    }
+
+    #[test]
+    fn cumulative_counts_add_up_from_the_previous_tick() {
+        let previous = CompartmentCounts::count(0, &population(), 0, 0, None);
+        let counts = CompartmentCounts::count(16, &population(), 2, 1, Some(&previous));
+        assert_eq!(counts.time_ms, 16);
+        assert_eq!(counts.new_infections, 2);
+        assert_eq!(counts.cumulative_infections, 6);
+        assert_eq!(counts.cumulative_deaths, 2);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::painter::Painter;
+use rgx::core::*;
+use rgx::math::*;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+// a vector snapshot of the arena, for publications and reports
+
+pub struct SvgDocument {
+    width: f32,
+    height: f32,
+    elements: String,
+}
+
+impl SvgDocument {
+    pub fn new(width: f32, height: f32, background: Rgba) -> SvgDocument {
+        let mut document = SvgDocument {
+            width,
+            height,
+            elements: String::new(),
+        };
+        document.fill_rect(
+            &Vector2::new(0., 0.),
+            &Vector2::new(width, height),
+            background,
+        );
+        document
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), io::Error> {
+        fs::write(
+            path,
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{}</svg>\n",
+                self.elements,
+                w = self.width,
+                h = self.height
+            ),
+        )
+    }
+}
+
+// an svg colour attribute, along with its opacity when it isn't opaque
+fn paint(attribute: &str, color: Rgba) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    let mut paint = format!(
+        "{}=\"#{:02x}{:02x}{:02x}\"",
+        attribute,
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    );
+    if color.a < 1. {
+        let _ = write!(paint, " {}-opacity=\"{}\"", attribute, color.a.max(0.));
+    }
+    paint
+}
+
+impl Painter for SvgDocument {
+    fn fill_rect(&mut self, bottom_left: &Vector2<f32>, top_right: &Vector2<f32>, color: Rgba) {
+        let _ = writeln!(
+            self.elements,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+            bottom_left.x.min(top_right.x),
+            bottom_left.y.min(top_right.y),
+            (top_right.x - bottom_left.x).abs(),
+            (top_right.y - bottom_left.y).abs(),
+            paint("fill", color)
+        );
+    }
+
+    fn stroke_rect(
+        &mut self,
+        bottom_left: &Vector2<f32>,
+        top_right: &Vector2<f32>,
+        width: f32,
+        color: Rgba,
+    ) {
+        let _ = writeln!(
+            self.elements,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke-width=\"{}\" {}/>",
+            bottom_left.x.min(top_right.x),
+            bottom_left.y.min(top_right.y),
+            (top_right.x - bottom_left.x).abs(),
+            (top_right.y - bottom_left.y).abs(),
+            width,
+            paint("stroke", color)
+        );
+    }
+
+    fn line(&mut self, start: &Vector2<f32>, end: &Vector2<f32>, width: f32, color: Rgba) {
+        let _ = writeln!(
+            self.elements,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke-width=\"{}\" {}/>",
+            start.x,
+            start.y,
+            end.x,
+            end.y,
+            width,
+            paint("stroke", color)
+        );
+    }
+
+    fn fill_circle(&mut self, center: &Vector2<f32>, radius: f32, color: Rgba) {
+        let _ = writeln!(
+            self.elements,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+            center.x,
+            center.y,
+            radius,
+            paint("fill", color)
+        );
+    }
+
+    fn stroke_circle(&mut self, center: &Vector2<f32>, radius: f32, width: f32, color: Rgba) {
+        let _ = writeln!(
+            self.elements,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke-width=\"{}\" {}/>",
+            center.x,
+            center.y,
+            radius,
+            width,
+            paint("stroke", color)
+        );
+    }
+}
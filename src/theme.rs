@@ -0,0 +1,98 @@
+use crate::organism::InfectionState;
+use rgx::core::*;
+// the colours organisms are drawn in, picked by name in the config and overridable per state
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub uninfected: Rgba,
+    pub infected: Rgba,
+    pub symptomatic: Rgba,
+    pub recovered: Rgba,
+    pub dead: Rgba,
+    pub blink_dead: bool, // dead organisms blink to black, which is tiring in long sessions
+}
+
+impl Theme {
+    // the colours the app has always used
+    pub fn classic() -> Theme {
+        Theme {
+            uninfected: Rgba::new(0.0, 0.5, 0.0, 1.0),
+            infected: Rgba::new(1.0, 0.0, 0.0, 1.0),
+            symptomatic: Rgba::new(0.6, 0.0, 0.0, 1.0),
+            recovered: Rgba::new(0.25, 0.25, 0.25, 1.0),
+            dead: Rgba::new(1.0, 0., 1.0, 1.0),
+            blink_dead: true,
+        }
+    }
+
+    // from the Okabe-Ito palette, which stays distinguishable with the common kinds of colour
+    // blindness. Infected and symptomatic differ in lightness as well as hue
+    pub fn colorblind() -> Theme {
+        Theme {
+            uninfected: rgb(0x00, 0x72, 0xb2),  // blue
+            infected: rgb(0xe6, 0x9f, 0x00),    // orange
+            symptomatic: rgb(0xd5, 0x5e, 0x00), // vermillion
+            recovered: rgb(0x59, 0x59, 0x59),   // gray
+            dead: rgb(0xcc, 0x79, 0xa7),        // reddish purple
+            blink_dead: true,
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "colorblind" => Some(Theme::colorblind()),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self, infection_state: InfectionState) -> Rgba {
+        match infection_state {
+            InfectionState::Uninfected => self.uninfected,
+            InfectionState::Infected => self.infected,
+            InfectionState::Symptomatic => self.symptomatic,
+            InfectionState::Recovered => self.recovered,
+            InfectionState::Dead => self.dead,
+        }
+    }
+}
+
+// `rrggbb` in hex, without the usual `#` as that starts a comment in scenario files
+pub fn parse_color(hex: &str) -> Option<Rgba> {
+    // from_str_radix would take a sign as well
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some(rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn rgb(r: u8, g: u8, b: u8) -> Rgba {
+    Rgba::new(r as f32 / 255., g as f32 / 255., b as f32 / 255., 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_parsed_from_hex() {
+        assert_eq!(parse_color("ff0000"), Some(Rgba::new(1., 0., 0., 1.)));
+        assert_eq!(parse_color("00FF00"), Some(Rgba::new(0., 1., 0., 1.)));
+        assert_eq!(parse_color("0072b2"), Some(Theme::colorblind().uninfected));
+    }
+
+    #[test]
+    fn malformed_colors_are_rejected() {
+        for hex in &["", "fff", "#ff0000", "ff00000", "gg0000", "+1ff00", "ff00é"] {
+            assert_eq!(parse_color(hex), None, "{}", hex);
+        }
+    }
+
+    #[test]
+    fn themes_are_named() {
+        assert!(Theme::named("classic").is_some());
+        assert!(Theme::named("colorblind").is_some());
+        assert!(Theme::named("rainbow").is_none());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::heatmap::HeatmapMode;
+use crate::options::Options;
+use crate::painter::Painter;
+use crate::playback::Playback;
+use crate::simulation_app::SimulationApp;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, event, execute, queue, terminal, ErrorKind};
+use rand::random;
+use rgx::core::*;
+use rgx::math::*;
+use std::io;
+use std::io::{Stdout, Write};
+use std::time::{Duration, Instant};
+// draws the arena into a terminal with half-block characters, for machines without a display
+
+// how long to wait for a key before drawing the next frame
+const FRAME_MS: u64 = 50;
+const HALF_BLOCK: char = '\u{2580}'; // upper half block, its foreground is the top pixel
+
+// the arena downsampled to terminal pixels, two per character cell. Every pixel shows the
+// average colour of what was drawn over it, so single organisms don't disappear
+struct TerminalImage {
+    width: usize,
+    height: usize,
+    scale: f32,          // terminal pixels per arena pixel
+    sums: Vec<[f32; 4]>, // red, green and blue weighted by alpha, and the number of shapes
+}
+
+impl TerminalImage {
+    fn new(width: usize, height: usize, scale: f32) -> TerminalImage {
+        TerminalImage {
+            width,
+            height,
+            scale,
+            sums: vec![[0.; 4]; width * height],
+        }
+    }
+
+    fn color(&self, x: usize, y: usize) -> Color {
+        let sum = match self.sums.get(y * self.width + x) {
+            Some(sum) if x < self.width && sum[3] > 0. => sum,
+            _ => return Color::Rgb { r: 0, g: 0, b: 0 },
+        };
+        let channel = |value: f32| (value / sum[3] * 255.).round().clamp(0., 255.) as u8;
+        Color::Rgb {
+            r: channel(sum[0]),
+            g: channel(sum[1]),
+            b: channel(sum[2]),
+        }
+    }
+
+    fn add(&mut self, left: f32, top: f32, right: f32, bottom: f32, color: Rgba) {
+        let clip = |value: f32, size: usize| ((value * self.scale).max(0.) as usize).min(size);
+        let (x0, x1) = (clip(left, self.width - 1), clip(right, self.width - 1));
+        let (y0, y1) = (clip(top, self.height - 1), clip(bottom, self.height - 1));
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let sum = &mut self.sums[y * self.width + x];
+                sum[0] += color.r * color.a;
+                sum[1] += color.g * color.a;
+                sum[2] += color.b * color.a;
+                sum[3] += 1.;
+            }
+        }
+    }
+}
+
+impl Painter for TerminalImage {
+    fn fill_rect(&mut self, bottom_left: &Vector2<f32>, top_right: &Vector2<f32>, color: Rgba) {
+        self.add(
+            bottom_left.x.min(top_right.x),
+            bottom_left.y.min(top_right.y),
+            bottom_left.x.max(top_right.x),
+            bottom_left.y.max(top_right.y),
+            color,
+        );
+    }
+
+    // outlines are far thinner than a terminal pixel
+    fn stroke_rect(
+        &mut self,
+        _bottom_left: &Vector2<f32>,
+        _top_right: &Vector2<f32>,
+        _width: f32,
+        _color: Rgba,
+    ) {
+    }
+
+    fn line(&mut self, start: &Vector2<f32>, end: &Vector2<f32>, _width: f32, color: Rgba) {
+        let steps = (start.distance(*end) * self.scale).ceil().max(1.) as usize;
+        for step in 0..=steps {
+            let point = *start + (*end - *start) * (step as f32 / steps as f32);
+            self.add(point.x, point.y, point.x, point.y, color);
+        }
+    }
+
+    fn fill_circle(&mut self, center: &Vector2<f32>, radius: f32, color: Rgba) {
+        self.add(
+            center.x - radius,
+            center.y - radius,
+            center.x + radius,
+            center.y + radius,
+            color,
+        );
+    }
+
+    fn stroke_circle(&mut self, center: &Vector2<f32>, radius: f32, _width: f32, color: Rgba) {
+        let steps = (radius * std::f32::consts::TAU * self.scale).ceil().max(4.) as usize;
+        for step in 0..steps {
+            let angle = step as f32 / steps as f32 * std::f32::consts::TAU;
+            let point = *center + Vector2::new(angle.cos(), angle.sin()) * radius;
+            self.add(point.x, point.y, point.x, point.y, color);
+        }
+    }
+}
+
+pub fn run(mut simulation: SimulationApp, options: &Options) -> Result<(), io::Error> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode().map_err(terminal_error)?;
+    execute!(stdout, EnterAlternateScreen, cursor::Hide).map_err(terminal_error)?;
+
+    let result = run_loop(&mut stdout, &mut simulation).map_err(terminal_error);
+
+    // restore the terminal even when the loop failed
+    let restored = execute!(stdout, ResetColor, cursor::Show, LeaveAlternateScreen)
+        .and_then(|_| terminal::disable_raw_mode())
+        .map_err(terminal_error);
+    let result = result.and(restored).and_then(|_| {
+        if let Some(path) = options.screenshot_path.as_ref() {
+            simulation.rasterize(HeatmapMode::Off).write_png(path)?;
+        }
+        if let Some(path) = options.svg_path.as_ref() {
+            simulation.export_svg(HeatmapMode::Off).write(path)?;
+        }
+        Ok(())
+    });
+    simulation.finish();
+    result
+}
+
+fn run_loop(stdout: &mut Stdout, simulation: &mut SimulationApp) -> crossterm::Result<()> {
+    let mut playback = Playback::new();
+    let mut previous: Vec<(Color, Color)> = vec![];
+    let mut last_time = Instant::now();
+    // shown instead of the key hints until the next key, since printing would garble the screen
+    let mut message: Option<String> = None;
+
+    loop {
+        if event::poll(Duration::from_millis(FRAME_MS))? {
+            match event::read()? {
+                Event::Key(KeyEvent { code, modifiers }) => {
+                    message = None;
+                    match code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                            return Ok(())
+                        }
+                        KeyCode::Char(' ') => playback.running = true,
+                        KeyCode::Char('p') => playback.running = false,
+                        KeyCode::Char('.') | KeyCode::Right => playback.step(simulation),
+                        KeyCode::Char('+') | KeyCode::Char('=') => playback.faster(),
+                        KeyCode::Char('-') => playback.slower(),
+                        KeyCode::Char('f') => playback.fast_forward = !playback.fast_forward,
+                        KeyCode::Char('r') => {
+                            let config = simulation.config().clone();
+                            let seed = simulation.seed();
+                            if let Err(error) = simulation.reset(config, seed) {
+                                message = Some(format!("failed to restart: {}", error));
+                            }
+                        }
+                        KeyCode::Char('n') => {
+                            let config = simulation.config().clone();
+                            if let Err(error) = simulation.reset(config, random()) {
+                                message = Some(format!("failed to restart: {}", error));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                // everything is drawn again at the new size
+                Event::Resize(_, _) => {
+                    previous.clear();
+                    queue!(stdout, ResetColor, Clear(ClearType::All))?;
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        let delta_ms = now.duration_since(last_time).as_millis() as i64;
+        last_time = now;
+        playback.advance(simulation, delta_ms);
+
+        draw(
+            stdout,
+            simulation,
+            &playback,
+            message.as_deref(),
+            &mut previous,
+        )?;
+    }
+}
+
+// only cells that changed since the previous frame are written, which keeps SSH sessions usable
+fn draw(
+    stdout: &mut Stdout,
+    simulation: &SimulationApp,
+    playback: &Playback,
+    message: Option<&str>,
+    previous: &mut Vec<(Color, Color)>,
+) -> crossterm::Result<()> {
+    let (columns, rows) = terminal::size()?;
+    let (columns, rows) = (columns as usize, rows as usize);
+    if columns == 0 || rows < 2 {
+        return Ok(());
+    }
+    // the last row holds the status line
+    let text_rows = rows - 1;
+    let arena_size = simulation.arena_size();
+    let scale = (columns as f32 / arena_size.x).min((text_rows * 2) as f32 / arena_size.y);
+    let mut image = TerminalImage::new(columns, text_rows * 2, scale);
+    simulation.render(&mut image, HeatmapMode::Off);
+
+    if previous.len() != columns * text_rows {
+        *previous = vec![(Color::Reset, Color::Reset); columns * text_rows];
+    }
+    let mut colors = None;
+    for row in 0..text_rows {
+        let mut positioned = false;
+        for column in 0..columns {
+            let cell = (
+                image.color(column, row * 2),
+                image.color(column, row * 2 + 1),
+            );
+            if previous[row * columns + column] == cell {
+                positioned = false;
+                continue;
+            }
+            previous[row * columns + column] = cell;
+            if !positioned {
+                queue!(stdout, cursor::MoveTo(column as u16, row as u16))?;
+                positioned = true;
+            }
+            if colors != Some(cell) {
+                queue!(
+                    stdout,
+                    SetForegroundColor(cell.0),
+                    SetBackgroundColor(cell.1)
+                )?;
+                colors = Some(cell);
+            }
+            queue!(stdout, Print(HALF_BLOCK))?;
+        }
+    }
+
+    let counts = simulation.latest_counts();
+    let status = format!(
+        "TIME {:.1} S  S {}  I {} ({} symptomatic)  R {}  D {}  {} {}x{}  seed {}  {}",
+        counts.time_ms as f32 / 1000.,
+        counts.susceptible,
+        counts.infected,
+        counts.symptomatic,
+        counts.recovered,
+        counts.dead,
+        if playback.running { "running" } else { "paused" },
+        playback.speed,
+        if playback.fast_forward { " fast forward" } else { "" },
+        simulation.seed(),
+        message.unwrap_or(
+            "[space] run [p] pause [.] step [+/-] speed [f] fast forward [r] restart [n] new seed [q] quit"
+        )
+    );
+    let status: String = status.chars().take(columns).collect();
+    queue!(
+        stdout,
+        ResetColor,
+        cursor::MoveTo(0, text_rows as u16),
+        Print(status),
+        Clear(ClearType::UntilNewLine)
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+// io::Error::other needs Rust 1.74
+#[allow(clippy::io_other_error)]
+fn terminal_error(error: ErrorKind) -> io::Error {
+    match error {
+        ErrorKind::IoError(error) => error,
+        error => io::Error::new(io::ErrorKind::Other, error),
+    }
+}
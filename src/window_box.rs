@@ -1,7 +1,23 @@
 use rgx::math::*;
 // implements the collision box as implemented through rgx
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum BoundaryMode {
+    Reflect, // organisms bounce off the window edges
+    Wrap,    // organisms leaving one edge reappear on the opposite side(torus)
+}
+
+// a barrier segment inside the window that organisms bounce off. Walls only affect movement,
+// an infection still reaches an organism touching the other side
+#[derive(Clone, Copy)]
+pub struct Wall {
+    pub start: Vector2<f32>,
+    pub end: Vector2<f32>,
+}
+
 pub struct WindowBox {
+    boundary_mode: BoundaryMode,
+    walls: Vec<Wall>,
     top_left: Vector2<f32>,
     top_right: Vector2<f32>,
     bottom_left: Vector2<f32>,
@@ -14,10 +30,12 @@ pub struct CollisionResult {
 }
 
 impl WindowBox {
-    pub fn new(width: i32, height: i32) -> WindowBox {
+    pub fn new(width: i32, height: i32, boundary_mode: BoundaryMode) -> WindowBox {
         let f_width = width as f32;
         let f_height = height as f32;
         WindowBox {
+            boundary_mode,
+            walls: vec![],
             top_left: Vector2::new(0.0, f_height),
             top_right: Vector2::new(f_width, f_height),
             bottom_left: Vector2::new(0.0, 0.0),
@@ -35,6 +53,15 @@ impl WindowBox {
             position.x + (distance * direction.x),
             position.y + (distance * direction.y),
         );
+        if let Some(result) = self.wall_collided(position, &new_position, direction) {
+            return result;
+        }
+        if self.boundary_mode == BoundaryMode::Wrap {
+            return CollisionResult {
+                position: self.wrap_position(&new_position),
+                direction: *direction,
+            };
+        }
         let top = self.top_collided(&position, &new_position, &direction);
         let bottom = self.bottom_collided(&position, &new_position, &direction);
         let left = self.left_collided(&position, &new_position, &direction);
@@ -55,6 +82,52 @@ impl WindowBox {
         }
     }
 
+    pub fn wrap_position(&self, position: &Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(
+            position.x.rem_euclid(self.top_right.x),
+            position.y.rem_euclid(self.top_right.y),
+        )
+    }
+
+    pub fn add_wall(&mut self, wall: Wall) {
+        self.walls.push(wall);
+    }
+
+    // removes every wall crossed by the segment from start to end
+    pub fn erase_walls(&mut self, start: &Vector2<f32>, end: &Vector2<f32>) {
+        self.walls
+            .retain(|wall| Self::line_collision(&wall.start, &wall.end, start, end).is_none());
+    }
+
+    pub fn walls(&self) -> &[Wall] {
+        &self.walls
+    }
+
+    fn wall_collided(
+        &self,
+        current: &Vector2<f32>,
+        projected: &Vector2<f32>,
+        direction: &Vector2<f32>,
+    ) -> Option<CollisionResult> {
+        // the first wall on the way, not the first one drawn
+        let (wall, _) = self
+            .walls
+            .iter()
+            .filter_map(|wall| {
+                Self::line_collision(&wall.start, &wall.end, current, projected)
+                    .map(|point| (wall, point.distance(*current)))
+            })
+            .min_by(|(_, first), (_, second)| first.partial_cmp(second).unwrap())?;
+        // mirror the direction across the wall
+        let along = (wall.end - wall.start).normalize();
+        let normal = Vector2::new(-along.y, along.x);
+        let new_direction = *direction - normal * (2. * Vector2::dot(*direction, normal));
+        Some(CollisionResult {
+            position: *current,
+            direction: new_direction,
+        })
+    }
+
     fn top_collided(
         &self,
         current: &Vector2<f32>,
@@ -150,8 +223,8 @@ impl WindowBox {
 
             if (r >= 0.0 && r <= 1.0) && (s >= 0.0 && s <= 1.0) {
                 Some(Vector2::new(
-                    p1_1.x + (num1 * (p1_2.x - p1_1.x)),
-                    p1_1.y + (num1 * (p1_2.y - p1_1.y)),
+                    p1_1.x + (r * (p1_2.x - p1_1.x)),
+                    p1_1.y + (r * (p1_2.y - p1_1.y)),
                 ))
             } else {
                 None
@@ -159,3 +232,75 @@ impl WindowBox {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(start: (f32, f32), end: (f32, f32)) -> Wall {
+        Wall {
+            start: Vector2::new(start.0, start.1),
+            end: Vector2::new(end.0, end.1),
+        }
+    }
+
+    #[test]
+    fn crossing_lines_collide_where_they_cross() {
+        let point = WindowBox::line_collision(
+            &Vector2::new(0., 0.),
+            &Vector2::new(10., 0.),
+            &Vector2::new(4., -1.),
+            &Vector2::new(4., 3.),
+        );
+        assert_eq!(point, Some(Vector2::new(4., 0.)));
+    }
+
+    #[test]
+    fn separate_lines_do_not_collide() {
+        // parallel
+        let point = WindowBox::line_collision(
+            &Vector2::new(0., 0.),
+            &Vector2::new(10., 0.),
+            &Vector2::new(0., 1.),
+            &Vector2::new(10., 1.),
+        );
+        assert_eq!(point, None);
+        // crossing only if the second line went on
+        let point = WindowBox::line_collision(
+            &Vector2::new(0., 0.),
+            &Vector2::new(10., 0.),
+            &Vector2::new(4., 3.),
+            &Vector2::new(4., 1.),
+        );
+        assert_eq!(point, None);
+    }
+
+    #[test]
+    fn the_nearest_wall_is_hit() {
+        let mut window_box = WindowBox::new(100, 100, BoundaryMode::Reflect);
+        window_box.add_wall(wall((30., 0.), (30., 100.)));
+        window_box.add_wall(wall((20., 0.), (30., 100.)));
+        let direction = Vector2::new(1., 0.);
+        let result = window_box
+            .wall_collided(&Vector2::new(10., 10.), &Vector2::new(40., 10.), &direction)
+            .unwrap();
+        // mirrored across the slanted wall rather than the upright one drawn first
+        assert_eq!(result.position, Vector2::new(10., 10.));
+        assert!(result.direction.y != 0.);
+        assert!(result.direction.x < 0.);
+    }
+
+    #[test]
+    fn walls_reflect_the_direction() {
+        let mut window_box = WindowBox::new(100, 100, BoundaryMode::Wrap);
+        window_box.add_wall(wall((50., 0.), (50., 100.)));
+        let result =
+            window_box.collided_velocity(&Vector2::new(45., 10.), 10., &Vector2::new(1., 0.));
+        assert_eq!(result.position, Vector2::new(45., 10.));
+        assert_eq!(result.direction, Vector2::new(-1., 0.));
+
+        let result =
+            window_box.collided_velocity(&Vector2::new(35., 10.), 10., &Vector2::new(1., 0.));
+        assert_eq!(result.position, Vector2::new(45., 10.));
+    }
+}